@@ -0,0 +1,61 @@
+use crate::in_repo_db;
+use crate::in_repo_db::commits;
+use crate::in_repo_db::structs::TicketId;
+use std::collections::BTreeSet;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// Closes the tickets named by `Fixes T0003`-style keywords in the commits since `since`, or
+/// since the last run when it is not given. Remembering HEAD afterwards makes it safe to run
+/// from a post-merge hook. The first run only remembers HEAD, so old commits do not close
+/// tickets that were reopened since.
+pub(crate) fn process_commits(since: Option<&str>) -> Result<()> {
+    let since = match since {
+        Some(since) => Some(since.to_owned()),
+        None => in_repo_db::load_last_processed_commit()?,
+    };
+    let Some(since) = since else {
+        if let Some(head) = in_repo_db::head_commit()? {
+            in_repo_db::save_last_processed_commit(&head)?;
+            println!(
+                "First run: commits up to {} will not be processed, pass --since to process \
+                 earlier ones.",
+                &head[..7.min(head.len())]
+            );
+        }
+        return Ok(());
+    };
+    let (head, closing_commits) = commits::closing_commits(Some(&since))?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let mut closed: BTreeSet<&TicketId> = BTreeSet::new();
+    for commit in &closing_commits {
+        let short = &commit.commit[..7.min(commit.commit.len())];
+        // Like the commit-msg hook, ignore words such as `UTF-8` that only look like IDs.
+        for id in commit
+            .tickets
+            .iter()
+            .filter(|id| in_repo_db.is_ticket_id_format(id))
+        {
+            let Some(ticket) = in_repo_db.get_ticket(id) else {
+                println!("Skipping {id} referenced by {short}: no such ticket");
+                continue;
+            };
+            if !ticket.is_open() || closed.contains(ticket.id()) {
+                continue;
+            }
+            let mut ticket = ticket.clone();
+            ticket.close(Some(commit.commit.clone()));
+            in_repo_db::verify_and_write(&ticket)?;
+            println!("Closed {id} ({short} {})", commit.summary);
+            closed.insert(id);
+        }
+    }
+    if closed.is_empty() {
+        println!("No tickets to close.");
+    }
+    if let Some(head) = head {
+        in_repo_db::save_last_processed_commit(&head)?;
+    }
+    Ok(())
+}
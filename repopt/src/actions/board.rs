@@ -0,0 +1,186 @@
+use crate::in_repo_db;
+use crate::in_repo_db::structs::{ProjectId, Ticket, TicketFilters, TicketSortOrder, TicketStatus};
+use clap::ValueEnum;
+use std::fmt::Write;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+const DEFAULT_WIDTH: usize = 80;
+/// Narrower columns than this leave too little of each title to be useful.
+const MIN_COLUMN_WIDTH: usize = 20;
+const COLUMN_GAP: &str = " | ";
+const ELLIPSIS: &str = "...";
+
+struct BoardColumn {
+    header: String,
+    cards: Vec<String>,
+}
+
+pub(crate) fn print_board(project: Option<&ProjectId>) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let project = match project {
+        Some(project) => Some(super::find_project(&in_repo_db, project)?.id()),
+        None => None,
+    };
+    let columns: Vec<BoardColumn> = TicketStatus::value_variants()
+        .iter()
+        .map(|status| {
+            let mut iter = Box::new(in_repo_db.iter_tickets().with_status(status.clone()))
+                as Box<dyn Iterator<Item = &Ticket>>;
+            if let Some(project) = project {
+                iter = iter.for_project(project.clone());
+            }
+            let mut tickets: Vec<&Ticket> = iter.collect();
+            tickets.sort_by(|a, b| TicketSortOrder::Priority.compare(a, b));
+            BoardColumn {
+                header: format!("{status} ({})", tickets.len()),
+                cards: tickets
+                    .iter()
+                    .map(|ticket| format!("{} {}", ticket.id(), ticket.title()))
+                    .collect(),
+            }
+        })
+        .collect();
+    println!("{}", render_board(&columns, terminal_width()));
+    Ok(())
+}
+
+/// `COLUMNS` wins so the board can be sized for pasting; otherwise ask the terminal.
+fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .or_else(|| {
+            ratatui::crossterm::terminal::size()
+                .ok()
+                .map(|(width, _)| usize::from(width))
+        })
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+fn render_board(columns: &[BoardColumn], width: usize) -> String {
+    let gaps = COLUMN_GAP.len() * columns.len().saturating_sub(1);
+    let column_width = width.saturating_sub(gaps) / columns.len().max(1);
+    if column_width < MIN_COLUMN_WIDTH {
+        return render_vertical(columns, width);
+    }
+    let mut out = String::new();
+    let row = |mut cells: Vec<String>| {
+        while cells.last().is_some_and(String::is_empty) {
+            cells.pop();
+        }
+        let cells: Vec<String> = cells
+            .iter()
+            .map(|cell| pad(&truncate(cell, column_width), column_width))
+            .collect();
+        cells.join(COLUMN_GAP).trim_end().to_owned()
+    };
+    let _ = writeln!(
+        out,
+        "{}",
+        row(columns.iter().map(|c| c.header.clone()).collect())
+    );
+    let _ = writeln!(
+        out,
+        "{}",
+        row(columns.iter().map(|_| "-".repeat(column_width)).collect())
+    );
+    let rows = columns.iter().map(|c| c.cards.len()).max().unwrap_or(0);
+    for index in 0..rows {
+        let cells = columns
+            .iter()
+            .map(|c| c.cards.get(index).cloned().unwrap_or_default())
+            .collect();
+        let _ = writeln!(out, "{}", row(cells));
+    }
+    out.trim_end().to_owned()
+}
+
+fn render_vertical(columns: &[BoardColumn], width: usize) -> String {
+    let mut out = String::new();
+    for column in columns {
+        let _ = writeln!(out, "{}", truncate(&column.header, width));
+        for card in &column.cards {
+            let _ = writeln!(out, "  {}", truncate(card, width.saturating_sub(2)));
+        }
+        out.push('\n');
+    }
+    out.trim_end().to_owned()
+}
+
+/// Cuts `text` down to `width` terminal columns. Widths are measured in columns rather than
+/// characters, as wide characters such as CJK take up two.
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_owned();
+    }
+    let available = width.saturating_sub(ELLIPSIS.len());
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        used += c.width().unwrap_or(0);
+        if used > available {
+            break;
+        }
+        truncated.push(c);
+    }
+    truncated.push_str(&ELLIPSIS[..width.min(ELLIPSIS.len())]);
+    truncated
+}
+
+/// Pads `text` with spaces to `width` terminal columns.
+fn pad(text: &str, width: usize) -> String {
+    format!("{text}{}", " ".repeat(width.saturating_sub(text.width())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<BoardColumn> {
+        vec![
+            BoardColumn {
+                header: "Backlog (2)".to_owned(),
+                cards: vec![
+                    "T0001 Fix the login page redirect loop".to_owned(),
+                    "T0002 Docs".to_owned(),
+                ],
+            },
+            BoardColumn {
+                header: "Closed (1)".to_owned(),
+                cards: vec!["T0003 Search".to_owned()],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_render_board_columns() {
+        let board = render_board(&columns(), 53);
+        let lines: Vec<&str> = board.lines().collect();
+        assert_eq!(lines[0], format!("{:<25} | Closed (1)", "Backlog (2)"));
+        assert_eq!(lines[1], format!("{0} | {0}", "-".repeat(25)));
+        assert_eq!(lines[2], "T0001 Fix the login pa... | T0003 Search");
+        assert_eq!(lines[3], "T0002 Docs");
+    }
+
+    #[test]
+    fn test_render_board_falls_back_to_vertical() {
+        let board = render_board(&columns(), 30);
+        assert_eq!(
+            board,
+            "Backlog (2)\n  T0001 Fix the login page ...\n  T0002 Docs\n\nClosed (1)\n  T0003 Search"
+        );
+    }
+
+    #[test]
+    fn test_truncate_counts_wide_characters() {
+        let card = "T0004 修复登录页面";
+        assert_eq!(truncate(card, 20), card);
+        assert_eq!(truncate(card, 11), "T0004 修...");
+        assert_eq!(truncate(card, 12), "T0004 修...");
+        assert_eq!(truncate(card, 2), "..");
+        assert_eq!(pad("修复", 6), "修复  ");
+    }
+}
@@ -0,0 +1,106 @@
+use crate::config::CONFIG;
+use crate::in_repo_db;
+use crate::in_repo_db::commits;
+use crate::in_repo_db::structs::{Ticket, TicketId, TicketStatus};
+use crate::output_formatter::GenerateOutputFormat;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// Slugs are cut at a word boundary once they pass this many characters.
+const MAX_SLUG_LENGTH: usize = 40;
+
+pub(crate) fn start_ticket(id: String) -> Result<()> {
+    let settings = in_repo_db::load_settings()?;
+    let mut ticket = super::get_ticket_by_id(id)?;
+    if !ticket.is_open() {
+        return Err(From::from(format!(
+            "{} is closed, reopen it first.",
+            ticket.id()
+        )));
+    }
+    let branch = branch_name(&settings.branches.pattern, &ticket)?;
+    in_repo_db::check_new_branch(&branch)?;
+    ticket.set_status(TicketStatus::InProgress);
+    ticket.assign(in_repo_db::current_user()?);
+    // Checking out a branch at HEAD leaves the working tree as it is, so the ticket is written
+    // first and nothing but the checkout itself can fail once the branch exists.
+    in_repo_db::verify_and_write(&ticket)?;
+    in_repo_db::create_and_checkout_branch(&branch)?;
+    println!("Switched to branch {branch}");
+    println!("Started {}", ticket.id());
+    Ok(())
+}
+
+pub(crate) fn current_ticket() -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let branch = in_repo_db::current_branch()?.ok_or("HEAD is not on a branch.")?;
+    let id = branch_ticket()?
+        .ok_or_else(|| format!("The branch {branch} does not belong to a ticket."))?;
+    let ticket = super::get_ticket_by_id(id.to_string())?;
+    println!("{}", config.formatter.try_format_single(ticket)?);
+    Ok(())
+}
+
+/// The existing ticket the checked out branch is named after.
+pub(super) fn branch_ticket() -> Result<Option<TicketId>> {
+    let Some(id) = in_repo_db::current_branch()?
+        .as_deref()
+        .and_then(commits::branch_ticket)
+    else {
+        return Ok(None);
+    };
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    Ok(in_repo_db.get_ticket(&id).is_some().then_some(id))
+}
+
+fn branch_name(pattern: &str, ticket: &Ticket) -> Result<String> {
+    if !pattern.contains("{id}") {
+        return Err(From::from(
+            "branches.pattern must contain {id} so the branch can be traced back to its ticket.",
+        ));
+    }
+    Ok(pattern
+        .replace("{id}", &ticket.id().to_string())
+        .replace("{slug}", &slugify(&ticket.title().to_string()))
+        .replace("{type}", &slugify(&ticket.ticket_type().to_string())))
+}
+
+/// `Fix login crash!` becomes `fix-login-crash`.
+fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for word in text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+    {
+        if !slug.is_empty() && slug.len() + word.len() >= MAX_SLUG_LENGTH {
+            break;
+        }
+        if !slug.is_empty() {
+            slug.push('-');
+        }
+        slug.push_str(&word.to_lowercase());
+    }
+    slug
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_repo_db::structs::tests::ticket_fixture;
+
+    #[test]
+    fn test_branch_name() {
+        let ticket = ticket_fixture("T0001", "Fix login crash on Safari!", TicketStatus::Backlog);
+        assert_eq!(
+            branch_name("{id}-{slug}", &ticket).unwrap(),
+            "T0001-fix-login-crash-on-safari"
+        );
+        assert_eq!(branch_name("{type}/{id}", &ticket).unwrap(), "bug/T0001");
+        assert!(branch_name("{slug}", &ticket).is_err());
+        assert_eq!(
+            slugify("A very long title that goes on and on and on forever"),
+            "a-very-long-title-that-goes-on-and-on"
+        );
+    }
+}
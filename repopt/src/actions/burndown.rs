@@ -0,0 +1,270 @@
+use super::get_ticket_by_id;
+use super::sprints::today;
+use crate::config::CONFIG;
+use crate::in_repo_db;
+use crate::in_repo_db::history;
+use crate::in_repo_db::settings::EstimateUnit;
+use crate::in_repo_db::structs::{MilestoneId, ProjectId, Ticket, TicketId};
+use crate::output_formatter::GenerateOutputFormat;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::Serialize;
+use std::fmt::Write;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+const CHART_HEIGHT: u32 = 10;
+
+/// Renderings of the burndown, besides JSON and YAML.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum BurndownOutput {
+    /// Comma separated values, one row per day.
+    Csv,
+    /// Plain text chart.
+    Chart,
+}
+
+pub(crate) fn estimate_ticket(id: String, estimate: Option<u32>) -> Result<()> {
+    let settings = in_repo_db::load_settings()?;
+    let mut ticket = get_ticket_by_id(id)?;
+    ticket.set_estimate(estimate);
+    in_repo_db::verify_and_write(&ticket)?;
+    match estimate {
+        Some(estimate) => println!(
+            "Estimated {} at {estimate} {}",
+            ticket.id(),
+            settings.estimates.unit
+        ),
+        None => println!("Cleared the estimate of {}", ticket.id()),
+    }
+    Ok(())
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct BurndownDay {
+    date: NaiveDate,
+    remaining: u32,
+    completed: u32,
+    open_tickets: usize,
+}
+
+#[derive(Serialize, Debug)]
+struct Burndown {
+    scope: String,
+    unit: EstimateUnit,
+    /// Tickets in scope that have no estimate and so never show up in the remaining work.
+    unestimated: Vec<TicketId>,
+    days: Vec<BurndownDay>,
+}
+
+/// Every known state of one ticket, oldest first, each paired with when it took effect.
+type Timeline = Vec<(DateTime<Utc>, Option<Ticket>)>;
+
+pub(crate) fn burndown(
+    milestone: Option<&MilestoneId>,
+    project: Option<&ProjectId>,
+    output: Option<BurndownOutput>,
+) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let settings = in_repo_db::load_settings()?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let (scope, tickets): (String, Vec<&Ticket>) = match (milestone, project) {
+        (Some(milestone), _) => {
+            in_repo_db
+                .get_milestone(milestone)
+                .ok_or("Milestone not found.")?;
+            let tickets = in_repo_db
+                .iter_tickets()
+                .filter(|ticket| ticket.milestone() == Some(milestone))
+                .collect();
+            (milestone.to_string(), tickets)
+        }
+        (None, Some(project)) => {
+            let project = super::find_project(&in_repo_db, project)?.id();
+            let tickets = in_repo_db
+                .iter_tickets()
+                .filter(|ticket| ticket.get_project_id() == project)
+                .collect();
+            (project.to_string(), tickets)
+        }
+        (None, None) => return Err(From::from("Pass either --milestone or --project.")),
+    };
+    let mut histories = history::ticket_histories()?;
+    let mut timelines = Vec::new();
+    for ticket in &tickets {
+        // The working tree counts as the latest state, so uncommitted edits show up today.
+        let mut timeline: Timeline = histories
+            .remove(ticket.id())
+            .unwrap_or_default()
+            .into_iter()
+            .rev()
+            .map(|revision| (revision.time, revision.ticket))
+            .collect();
+        timeline.push((Utc::now(), Some((*ticket).clone())));
+        timelines.push(timeline);
+    }
+    let burndown = Burndown {
+        scope,
+        unit: settings.estimates.unit,
+        unestimated: tickets
+            .iter()
+            .filter(|ticket| ticket.estimate().is_none())
+            .map(|ticket| ticket.id().clone())
+            .collect(),
+        days: daily_series(&timelines, today()),
+    };
+    let out_string = match output {
+        Some(BurndownOutput::Csv) => to_csv(&burndown.days),
+        Some(BurndownOutput::Chart) => to_chart(&burndown),
+        None => config.formatter.try_format_single(&burndown)?,
+    };
+    println!("{out_string}");
+    Ok(())
+}
+
+/// Replays the timelines day by day, from the first recorded change up to `until`, summing
+/// the estimates of the tickets as they stood at the end of each day.
+fn daily_series(timelines: &[Timeline], until: NaiveDate) -> Vec<BurndownDay> {
+    let local_date = |time: &DateTime<Utc>| time.with_timezone(&Local).date_naive();
+    let Some(start) = timelines
+        .iter()
+        .filter_map(|timeline| timeline.first())
+        .map(|(time, _)| local_date(time))
+        .min()
+    else {
+        return Vec::new();
+    };
+    start
+        .iter_days()
+        .take_while(|date| *date <= until)
+        .map(|date| {
+            let mut day = BurndownDay {
+                date,
+                remaining: 0,
+                completed: 0,
+                open_tickets: 0,
+            };
+            for timeline in timelines {
+                let state = timeline
+                    .iter()
+                    .take_while(|(time, _)| local_date(time) <= date)
+                    .last()
+                    .and_then(|(_, ticket)| ticket.as_ref());
+                let Some(ticket) = state else {
+                    continue;
+                };
+                let estimate = ticket.estimate().unwrap_or_default();
+                if ticket.is_open() {
+                    day.remaining += estimate;
+                    day.open_tickets += 1;
+                } else {
+                    day.completed += estimate;
+                }
+            }
+            day
+        })
+        .collect()
+}
+
+fn to_csv(days: &[BurndownDay]) -> String {
+    let mut out = String::from("date,remaining,completed,open_tickets\n");
+    for day in days {
+        let _ = writeln!(
+            out,
+            "{},{},{},{}",
+            day.date, day.remaining, day.completed, day.open_tickets
+        );
+    }
+    out.trim_end().to_owned()
+}
+
+fn to_chart(burndown: &Burndown) -> String {
+    let mut out = format!("Remaining {} for {}\n", burndown.unit, burndown.scope);
+    let (Some(first), Some(last)) = (burndown.days.first(), burndown.days.last()) else {
+        out.push_str("No history yet.");
+        return out;
+    };
+    let max = burndown
+        .days
+        .iter()
+        .map(|day| day.remaining)
+        .max()
+        .unwrap_or_default()
+        .max(1);
+    let label_width = max.to_string().len();
+    for row in (1..=CHART_HEIGHT).rev() {
+        let label = match row {
+            CHART_HEIGHT => max.to_string(),
+            1 => "0".to_owned(),
+            _ => String::new(),
+        };
+        let bars: String = burndown
+            .days
+            .iter()
+            .map(|day| {
+                // A bar reaches this row once it covers more than the row below it.
+                if day.remaining * CHART_HEIGHT > (row - 1) * max {
+                    '#'
+                } else {
+                    ' '
+                }
+            })
+            .collect();
+        let _ = writeln!(out, "{label:>label_width$} |{}", bars.trim_end());
+    }
+    let _ = writeln!(
+        out,
+        "{:>label_width$} +{}",
+        "",
+        "-".repeat(burndown.days.len())
+    );
+    let _ = write!(
+        out,
+        "{:>label_width$}  {} .. {} ({} open, {} done)",
+        "", first.date, last.date, last.open_tickets, last.completed
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_repo_db::structs::tests::ticket_fixture;
+    use crate::in_repo_db::structs::TicketStatus;
+    use chrono::TimeZone;
+
+    /// The ticket as committed at noon on `day` of May 2024.
+    fn revision(day: u32, status: TicketStatus, estimate: u32) -> (DateTime<Utc>, Option<Ticket>) {
+        let mut ticket = ticket_fixture("T0001", "Title", status);
+        ticket.set_estimate(Some(estimate));
+        let time = Local
+            .with_ymd_and_hms(2024, 5, day, 12, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc);
+        (time, Some(ticket))
+    }
+
+    #[test]
+    fn test_daily_series() {
+        let timelines = vec![
+            vec![
+                revision(1, TicketStatus::Backlog, 3),
+                revision(3, TicketStatus::Closed, 3),
+            ],
+            vec![
+                revision(2, TicketStatus::Backlog, 5),
+                revision(2, TicketStatus::Backlog, 8),
+            ],
+        ];
+        let days = daily_series(&timelines, NaiveDate::from_ymd_opt(2024, 5, 4).unwrap());
+        let remaining: Vec<(u32, u32, usize)> = days
+            .iter()
+            .map(|day| (day.remaining, day.completed, day.open_tickets))
+            .collect();
+        assert_eq!(remaining, [(3, 0, 1), (11, 0, 2), (8, 3, 1), (8, 3, 1)]);
+        assert_eq!(
+            to_csv(&days[..1]),
+            "date,remaining,completed,open_tickets\n2024-05-01,3,0,1"
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use super::{get_projects, list_ticket_by_status};
 use crate::in_repo_db::structs::{
-    ProjectDescription, ProjectName, ProjectStub, Ticket, TicketDescription, TicketStatus,
-    TicketTitle, TicketType,
+    CommentBody, MilestoneDescription, MilestoneName, ProjectDescription, ProjectName, ProjectStub,
+    Ticket, TicketDescription, TicketPriority, TicketSeverity, TicketStatus, TicketTitle,
+    TicketType,
 };
-use inquire::{Select, Text};
+use inquire::{Editor, Select, Text};
 
 type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
 type Result<T> = std::result::Result<T, Error>;
@@ -18,6 +19,11 @@ pub(super) fn get_description() -> Result<TicketDescription> {
     Ok(description.into())
 }
 
+pub(super) fn get_comment_body() -> Result<CommentBody> {
+    let body = Editor::new("Enter Comment:").prompt()?;
+    Ok(body.trim().to_owned().into())
+}
+
 pub(super) fn get_proj_name() -> Result<ProjectName> {
     let title = Text::new("Enter Project Name:").prompt()?;
     Ok(title.into())
@@ -28,6 +34,16 @@ pub(super) fn get_proj_desc() -> Result<ProjectDescription> {
     Ok(title.into())
 }
 
+pub(super) fn get_milestone_name() -> Result<MilestoneName> {
+    let name = Text::new("Enter Milestone Name:").prompt()?;
+    Ok(name.into())
+}
+
+pub(super) fn get_milestone_desc() -> Result<MilestoneDescription> {
+    let description = Text::new("Enter Milestone Description:").prompt()?;
+    Ok(description.into())
+}
+
 pub(super) fn get_ticket_type() -> Result<TicketType> {
     let options = vec!["Bug", "Feature"];
     let ans = Select::new("Select Ticket Type:", options).prompt();
@@ -38,6 +54,32 @@ pub(super) fn get_ticket_type() -> Result<TicketType> {
     }
 }
 
+pub(super) fn get_ticket_priority() -> Result<TicketPriority> {
+    let options = vec![
+        TicketPriority::P0,
+        TicketPriority::P1,
+        TicketPriority::P2,
+        TicketPriority::P3,
+    ];
+    let ans = Select::new("Select Ticket Priority:", options)
+        .with_starting_cursor(2)
+        .prompt()?;
+    Ok(ans)
+}
+
+pub(super) fn get_ticket_severity() -> Result<TicketSeverity> {
+    let options = vec![
+        TicketSeverity::Critical,
+        TicketSeverity::Major,
+        TicketSeverity::Minor,
+        TicketSeverity::Trivial,
+    ];
+    let ans = Select::new("Select Bug Severity:", options)
+        .with_starting_cursor(1)
+        .prompt()?;
+    Ok(ans)
+}
+
 #[allow(dead_code)]
 pub(super) enum TicketStatusTypes {
     All,
@@ -0,0 +1,205 @@
+use super::branches::branch_ticket;
+use crate::in_repo_db;
+use crate::in_repo_db::commits;
+use crate::in_repo_db::structs::{TicketId, TicketStatus};
+use inquire::Confirm;
+use std::fs;
+use std::io::IsTerminal;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// Marks the scripts written by `hooks install`, so they can be replaced without `--force`.
+const HOOK_MARKER: &str = "# Installed by `repopt hooks install`";
+
+/// The installed scripts only forward to `repopt hook <name>`, so upgrading repopt upgrades
+/// the hooks. A missing repopt never blocks git.
+const HOOKS: [(&str, &str); 3] = [
+    ("commit-msg", "exec repopt hook commit-msg \"$@\""),
+    (
+        "prepare-commit-msg",
+        "exec repopt hook prepare-commit-msg \"$@\"",
+    ),
+    (
+        "post-checkout",
+        // git gives hooks no stdin, so read the answer to the prompt from the terminal.
+        "if [ -t 2 ]; then exec repopt hook post-checkout \"$@\" < /dev/tty; fi\n\
+         exec repopt hook post-checkout \"$@\"",
+    ),
+];
+
+pub(crate) fn install_hooks(force: bool) -> Result<()> {
+    let hooks_dir = in_repo_db::hooks_dir()?;
+    fs::create_dir_all(&hooks_dir)?;
+    for (name, _) in HOOKS {
+        let path = hooks_dir.join(name);
+        if path.exists() && !force && !fs::read_to_string(&path)?.contains(HOOK_MARKER) {
+            return Err(From::from(format!(
+                "{} already exists, pass --force to replace it.",
+                path.display()
+            )));
+        }
+    }
+    for (name, command) in HOOKS {
+        let path = hooks_dir.join(name);
+        fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\n{HOOK_MARKER}\ncommand -v repopt >/dev/null 2>&1 || exit 0\n{command}\n"
+            ),
+        )?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        println!("Installed {}", path.display());
+    }
+    Ok(())
+}
+
+/// `commit-msg`: rejects messages that reference unknown tickets, and with
+/// `hooks.require_reference` messages that reference none. Only IDs in a format the database
+/// uses count, so `UTF-8` is not taken for a ticket of a project keyed `UTF`.
+pub(crate) fn commit_msg_hook(message_file: &str) -> Result<()> {
+    let settings = in_repo_db::load_settings()?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let message = fs::read_to_string(message_file)?;
+    check_commit_message(
+        &message,
+        |id| in_repo_db.is_ticket_id_format(id),
+        |id| in_repo_db.get_ticket(id).is_some(),
+        settings.hooks.require_reference,
+    )
+}
+
+/// `prepare-commit-msg`: adds a `Refs:` trailer for the ticket the branch is named after,
+/// unless the message already mentions it.
+pub(crate) fn prepare_commit_msg_hook(message_file: &str, source: Option<&str>) -> Result<()> {
+    // Merges, squashes and amends already have a message worth keeping as it is.
+    if matches!(source, Some("merge" | "squash" | "commit")) {
+        return Ok(());
+    }
+    let Some(id) = branch_ticket()? else {
+        return Ok(());
+    };
+    let message = fs::read_to_string(message_file)?;
+    if let Some(message) = add_reference(&message, &id) {
+        fs::write(message_file, message)?;
+    }
+    Ok(())
+}
+
+/// `post-checkout`: offers to start work on the ticket the new branch is named after.
+pub(crate) fn post_checkout_hook(branch_checkout: bool) -> Result<()> {
+    if !branch_checkout {
+        return Ok(());
+    }
+    let Some(id) = branch_ticket()? else {
+        return Ok(());
+    };
+    let mut ticket = super::get_ticket_by_id(id.to_string())?;
+    if *ticket.status() != TicketStatus::Backlog {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        println!(
+            "{id} is still in the backlog, run `repopt edit {id} --status in-progress` to start it."
+        );
+        return Ok(());
+    }
+    let start = Confirm::new(&format!("Move {id} \"{}\" to In Progress?", ticket.title()))
+        .with_default(true)
+        .prompt()?;
+    if start {
+        ticket.set_status(TicketStatus::InProgress);
+        in_repo_db::verify_and_write(&ticket)?;
+        println!("Moved {id} to In Progress");
+    }
+    Ok(())
+}
+
+fn check_commit_message(
+    message: &str,
+    is_id: impl Fn(&TicketId) -> bool,
+    exists: impl Fn(&TicketId) -> bool,
+    require_reference: bool,
+) -> Result<()> {
+    let (content, _) = split_comments(message);
+    let mut ids = commits::referenced_ids(content);
+    ids.retain(is_id);
+    let unknown: Vec<String> = ids
+        .iter()
+        .filter(|id| !exists(id))
+        .map(ToString::to_string)
+        .collect();
+    if !unknown.is_empty() {
+        return Err(From::from(format!(
+            "The commit message references unknown ticket(s): {}",
+            unknown.join(", ")
+        )));
+    }
+    if require_reference && ids.is_empty() {
+        return Err(From::from(
+            "The commit message must reference a ticket, e.g. \"Refs: T0001\".",
+        ));
+    }
+    Ok(())
+}
+
+/// The message with a `Refs:` trailer for `id` added after its text and before git's comment
+/// lines, or `None` when it already mentions the ticket.
+fn add_reference(message: &str, id: &TicketId) -> Option<String> {
+    let (content, comments) = split_comments(message);
+    if commits::mentions(content, id) {
+        return None;
+    }
+    let content = content.trim_end();
+    // An empty message keeps its first line free for the summary.
+    let separator = if content.is_empty() { "\n" } else { "\n\n" };
+    Some(format!("{content}{separator}Refs: {id}\n{comments}"))
+}
+
+/// Splits off the `#` lines git appends to the message template, which it strips again later.
+fn split_comments(message: &str) -> (&str, &str) {
+    let mut offset = 0;
+    for line in message.split_inclusive('\n') {
+        if line.starts_with('#') {
+            return message.split_at(offset);
+        }
+        offset += line.len();
+    }
+    (message, "")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_commit_message() {
+        let is_id = |id: &TicketId| !id.0.starts_with("UTF");
+        let exists = |id: &TicketId| id.0 == "T0001";
+        let check = |message, require| check_commit_message(message, is_id, exists, require);
+        assert!(check("Fix T0001", true).is_ok());
+        assert!(check("Fix T0002", false).is_err());
+        assert!(check("Fix it", false).is_ok());
+        assert!(check("Fix it\n# Refs: T0002", true).is_err());
+        assert!(check("Fix UTF-8 titles", false).is_ok());
+        assert!(check("Fix UTF-8 titles", true).is_err());
+    }
+
+    #[test]
+    fn test_add_reference() {
+        let id = TicketId::from("T0001");
+        assert_eq!(
+            add_reference("Fix login\n# Please enter\n", &id).unwrap(),
+            "Fix login\n\nRefs: T0001\n# Please enter\n"
+        );
+        assert_eq!(
+            add_reference("\n# Please enter\n", &id).unwrap(),
+            "\nRefs: T0001\n# Please enter\n"
+        );
+        assert_eq!(add_reference("T0001: fix login\n", &id), None);
+    }
+}
@@ -0,0 +1,67 @@
+use super::get_ticket_by_id;
+use crate::config::CONFIG;
+use crate::in_repo_db;
+use crate::in_repo_db::structs::Label;
+use crate::output_formatter::GenerateOutputFormat;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+pub(crate) fn add_labels(id: String, labels: Vec<String>) -> Result<()> {
+    let labels = parse_labels(labels)?;
+    let settings = in_repo_db::load_settings()?;
+    settings.labels.validate(&labels)?;
+    let mut ticket = get_ticket_by_id(id)?;
+    ticket.add_labels(labels);
+    println!(
+        "Labels for {}: {}",
+        ticket.id(),
+        fmt_labels(ticket.labels())
+    );
+    in_repo_db::verify_and_write(&ticket)?;
+    Ok(())
+}
+
+pub(crate) fn remove_labels(id: String, labels: Vec<String>) -> Result<()> {
+    let labels = parse_labels(labels)?;
+    let mut ticket = get_ticket_by_id(id)?;
+    ticket.remove_labels(&labels);
+    println!(
+        "Labels for {}: {}",
+        ticket.id(),
+        fmt_labels(ticket.labels())
+    );
+    in_repo_db::verify_and_write(&ticket)?;
+    Ok(())
+}
+
+pub(crate) fn list_labels() -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let out_string = config
+        .formatter
+        .try_format_single(in_repo_db.label_counts())?;
+    println!("{out_string}");
+    Ok(())
+}
+
+pub(super) fn parse_labels(labels: Vec<String>) -> Result<Vec<Label>> {
+    labels
+        .into_iter()
+        .map(|label| {
+            let label = label.trim();
+            if label.is_empty() || label.contains(char::is_whitespace) {
+                return Err(From::from(format!("Invalid label: {label:?}")));
+            }
+            Ok(Label::from(label))
+        })
+        .collect()
+}
+
+fn fmt_labels<'a>(labels: impl IntoIterator<Item = &'a Label>) -> String {
+    labels
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,86 @@
+use crate::config::CONFIG;
+use crate::in_repo_db;
+use crate::in_repo_db::structs::{DanglingLink, LinkType, TicketId, TicketLink};
+use crate::output_formatter::GenerateOutputFormat;
+use serde::Serialize;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+pub(crate) fn link_tickets(id: String, link_type: LinkType, target: String) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let (id, target): (TicketId, TicketId) = (id.into(), target.into());
+    let mut ticket = in_repo_db
+        .get_ticket(&id)
+        .cloned()
+        .ok_or("Ticket not found.")?;
+    if in_repo_db.get_ticket(&target).is_none() {
+        return Err(From::from(format!("Linked ticket {target} not found.")));
+    }
+    if id == target {
+        return Err(From::from("A ticket cannot be linked to itself."));
+    }
+    let blocking = match link_type {
+        LinkType::Blocks => Some((&id, &target)),
+        LinkType::BlockedBy => Some((&target, &id)),
+        _ => None,
+    };
+    if let Some((upstream, downstream)) = blocking {
+        if let Some(path) = in_repo_db.find_blocking_path(upstream, downstream) {
+            let path: Vec<String> = path.iter().map(ToString::to_string).collect();
+            return Err(From::from(format!(
+                "{upstream} blocks {downstream} would create a cycle, as {}.",
+                path.join(" blocks ")
+            )));
+        }
+    }
+    let link = TicketLink::new(link_type, target);
+    if in_repo_db.get_links(&id).any(|existing| existing == &link) {
+        return Err(From::from(format!("{id} already {link}.")));
+    }
+    println!("{id} {link}");
+    ticket.add_link(link);
+    in_repo_db::verify_and_write(&ticket)?;
+    Ok(())
+}
+
+pub(crate) fn unlink_tickets(id: String, target: String) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let (id, target): (TicketId, TicketId) = (id.into(), target.into());
+    let mut unlinked = false;
+    for (source, other) in [(&id, &target), (&target, &id)] {
+        let Some(mut ticket) = in_repo_db.get_ticket(source).cloned() else {
+            continue;
+        };
+        if ticket.remove_links_to(other) {
+            in_repo_db::verify_and_write(&ticket)?;
+            unlinked = true;
+        }
+    }
+    if !unlinked {
+        return Err(From::from(format!("{id} and {target} are not linked.")));
+    }
+    println!("Unlinked {id} and {target}");
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct LinkReport {
+    dangling: Vec<DanglingLink>,
+    blocking_cycles: Vec<Vec<TicketId>>,
+}
+
+pub(crate) fn check_links() -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let report = LinkReport {
+        dangling: in_repo_db.dangling_links(),
+        blocking_cycles: in_repo_db.find_blocking_cycles(),
+    };
+    let out_string = config.formatter.try_format_single(&report)?;
+    println!("{out_string}");
+    if !report.dangling.is_empty() || !report.blocking_cycles.is_empty() {
+        return Err(From::from("Link problems found."));
+    }
+    Ok(())
+}
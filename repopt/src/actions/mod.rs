@@ -1,63 +1,260 @@
+mod autoclose;
+mod board;
+mod branches;
+mod burndown;
 mod get_user_input;
+mod hooks;
+mod labels;
+mod links;
+mod sprints;
+mod tree;
+mod worklog;
 
+pub(super) use autoclose::process_commits;
+pub(super) use board::print_board;
+pub(super) use branches::{current_ticket, start_ticket};
+pub(super) use burndown::{burndown, estimate_ticket, BurndownOutput};
 use get_user_input::TicketStatusTypes;
+pub(super) use hooks::{
+    commit_msg_hook, install_hooks, post_checkout_hook, prepare_commit_msg_hook,
+};
+pub(super) use labels::{add_labels, list_labels, remove_labels};
+pub(super) use links::{check_links, link_tickets, unlink_tickets};
+pub(super) use sprints::{
+    add_to_sprint, carry_over, list_sprints, new_sprint, remove_from_sprint, sprint_summary,
+};
+pub(super) use worklog::{log_time, start_timer, stop_timer, timesheet};
 
 use crate::config::CONFIG;
 use crate::in_repo_db;
+use crate::in_repo_db::commits::{self, LinkedCommit};
+use crate::in_repo_db::doctor;
+use crate::in_repo_db::history;
+use crate::in_repo_db::merge;
+use crate::in_repo_db::renumber;
+use crate::in_repo_db::structs::ids;
 use crate::in_repo_db::structs::{
-    Project, ProjectId, Ticket, TicketFilters, TicketStatus, TicketType,
+    Comment, GraphOutput, InRepoDB, Milestone, MilestoneId, MilestoneStatus, Project, ProjectId,
+    Ticket, TicketDetails, TicketFilters, TicketId, TicketPriority, TicketSeverity,
+    TicketSortOrder, TicketStatus, TicketType, User,
 };
 use crate::output_formatter::GenerateOutputFormat;
-use std::collections::HashMap;
+use chrono::NaiveDate;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fs;
+use std::path::Path;
 
 type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
 type Result<T> = std::result::Result<T, Error>;
 
-pub(super) fn list_all_tickets(
-    filter_on_status: Option<TicketStatus>,
-    filter_on_type: Option<TicketType>,
-    for_project: Option<ProjectId>,
-) -> Result<()> {
+#[derive(Debug, Default)]
+pub(super) struct ListFilters {
+    pub status: Option<TicketStatus>,
+    pub ticket_type: Option<TicketType>,
+    pub project: Option<ProjectId>,
+    pub assignee: Option<String>,
+    pub labels: Vec<String>,
+    pub blocked: bool,
+    pub unblocked: bool,
+    pub children_of: Option<String>,
+    pub milestone: Option<String>,
+}
+
+pub(super) fn list_all_tickets(filters: ListFilters, sort: TicketSortOrder) -> Result<()> {
     let config = CONFIG.get().ok_or("Config not initialized")?;
     let in_repo_db = in_repo_db::collect_in_repo_db();
     let binding = in_repo_db?;
     let mut iter = Box::new(binding.iter_tickets()) as Box<dyn Iterator<Item = &Ticket>>;
-    if let Some(status) = filter_on_status {
+    if let Some(status) = filters.status {
         iter = iter.with_status(status);
     }
-    if let Some(ticket_type) = filter_on_type {
+    if let Some(ticket_type) = filters.ticket_type {
         iter = iter.with_type(ticket_type);
     }
-    if let Some(project) = for_project {
-        iter = iter.for_project(project);
+    if let Some(project) = filters.project {
+        iter = iter.for_project(find_project(&binding, &project)?.id().clone());
+    }
+    if let Some(assignee) = filters.assignee {
+        iter = iter.assigned_to(resolve_user(&assignee)?);
+    }
+    if !filters.labels.is_empty() {
+        iter = iter.with_labels(labels::parse_labels(filters.labels)?);
+    }
+    if let Some(parent) = filters.children_of {
+        iter = iter.children_of(parent.into());
+    }
+    if let Some(milestone) = filters.milestone {
+        iter = iter.in_milestone(milestone.into());
     }
+    if filters.blocked {
+        iter = iter.blocked(&binding);
+    }
+    if filters.unblocked {
+        iter = iter.unblocked(&binding);
+    }
+    let mut tickets = iter.collect::<Vec<&Ticket>>();
+    tickets.sort_by(|a, b| sort.compare(a, b));
+    let out_string = config.formatter.try_format_multiple(&tickets)?;
+    println!("{out_string}");
+    Ok(())
+}
+
+pub(super) fn show_history(id: String, diff: Option<&[String]>) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let id = TicketId::from(id);
+    let out_string = match diff {
+        Some([from, to]) => {
+            let old = history::ticket_at_revision(&id, from)?;
+            let new = history::ticket_at_revision(&id, to)?;
+            if old.is_none() && new.is_none() {
+                return Err(From::from(format!(
+                    "{id} exists in neither {from} nor {to}."
+                )));
+            }
+            config
+                .formatter
+                .try_format_multiple(&history::diff_tickets(old.as_ref(), new.as_ref()))?
+        }
+        Some(_) => return Err(From::from("--diff takes exactly two revisions.")),
+        None => {
+            let change_log = history::change_log(&id)?;
+            if change_log.is_empty() {
+                return Err(From::from(format!("No committed history for {id}.")));
+            }
+            config.formatter.try_format_multiple(&change_log)?
+        }
+    };
+    println!("{out_string}");
+    Ok(())
+}
+
+pub(super) fn blame_ticket(id: String) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let ticket = get_ticket_by_id(id)?;
     let out_string = config
         .formatter
-        .try_format_multiple(&iter.collect::<Vec<&Ticket>>())?;
+        .try_format_multiple(&history::blame(&ticket)?)?;
     println!("{out_string}");
     Ok(())
 }
 
-pub(super) fn show_ticket(id: String) -> Result<()> {
+pub(super) fn show_ticket(id: String, as_tree: bool, with_commits: bool) -> Result<()> {
+    #[derive(Serialize)]
+    struct TicketWithCommits<'a> {
+        #[serde(flatten)]
+        details: TicketDetails<'a>,
+        commits: Vec<LinkedCommit>,
+    }
+
     let config = CONFIG.get().ok_or("Config not initialized")?;
     let in_repo_db = in_repo_db::collect_in_repo_db();
     let in_repo_db = in_repo_db?;
-    let ticket = in_repo_db.get_ticket(&id.into());
+    ids::validate_id("ticket", &id)?;
+    let id = TicketId::from(id);
+    let id = match in_repo_db.find_ticket(&id) {
+        Some(ticket) => ticket.id().clone(),
+        None => id,
+    };
+    if as_tree {
+        println!("{}", tree::render_tree(&in_repo_db, &id)?);
+        return Ok(());
+    }
+    let ticket = in_repo_db.get_ticket_details(&id);
     if let Some(ticket) = ticket {
-        let out_string = config.formatter.try_format_single(ticket)?;
+        let out_string = if with_commits {
+            config.formatter.try_format_single(TicketWithCommits {
+                details: ticket,
+                commits: commits::linked_commits(&id)?,
+            })?
+        } else {
+            config.formatter.try_format_single(ticket)?
+        };
         println!("{out_string}");
     } else {
         return Err(From::from("Ticket not found."));
+    }
+    Ok(())
+}
+
+pub(super) fn list_commits(id: String) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let ticket = get_ticket_by_id(id)?;
+    let commits = commits::linked_commits(ticket.id())?;
+    if commits.is_empty() {
+        return Err(From::from(format!("No commits reference {}.", ticket.id())));
+    }
+    println!("{}", config.formatter.try_format_multiple(&commits)?);
+    Ok(())
+}
+
+pub(super) fn export_graph(query: Option<&str>, output: Option<GraphOutput>) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let graph = in_repo_db.build_graph(query)?;
+    let out_string = match output {
+        Some(GraphOutput::Dot) => graph.to_dot(),
+        Some(GraphOutput::Mermaid) => graph.to_mermaid(),
+        None => config.formatter.try_format_single(&graph)?,
     };
+    println!("{out_string}");
     Ok(())
 }
 
 pub(super) fn init_new_repository() -> Result<()> {
     println!("Initializing a new repository");
-    let in_repo_db = in_repo_db::collect_in_repo_db();
-    // in_repo_db should be an error here
-    if in_repo_db.is_ok() {
-        return Err(From::from("Repository already exists."));
+    // Creates the IRDB directories when they are missing; running it again is harmless.
+    in_repo_db::collect_in_repo_db()?;
+    in_repo_db::register_merge_driver()?;
+    println!("Registered the repopt merge driver for ticket files");
+    Ok(())
+}
+
+pub(super) fn renumber_tickets() -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let strategy = in_repo_db::load_settings()?.ids.strategy;
+    let renumberings = renumber::renumber(strategy)?;
+    if renumberings.is_empty() {
+        println!("No duplicate ticket IDs found.");
+        return Ok(());
+    }
+    println!("{}", config.formatter.try_format_multiple(&renumberings)?);
+    Ok(())
+}
+
+pub(super) fn run_doctor(fix: bool) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let problems = doctor::diagnose(fix)?;
+    if problems.is_empty() {
+        println!("No problems found.");
+        return Ok(());
+    }
+    println!("{}", config.formatter.try_format_multiple(&problems)?);
+    let remaining = problems.iter().filter(|problem| !problem.fixed).count();
+    if remaining > 0 {
+        // The fixes are kept even though the command fails, so commit them here.
+        if let Some((commit, message)) = in_repo_db::commit_written_files("doctor")? {
+            println!("[{commit}] {message}");
+        }
+        return Err(From::from(format!("{remaining} problem(s) left to fix.")));
+    }
+    Ok(())
+}
+
+/// Called by git as `repopt merge-driver %O %A %B`: merges the tickets field by field and
+/// leaves the result in `ours`, failing when fields conflict so git reports the file.
+pub(super) fn merge_driver(base: &Path, ours: &Path, theirs: &Path) -> Result<()> {
+    let merge = merge::merge_tickets(
+        &fs::read_to_string(base)?,
+        &fs::read_to_string(ours)?,
+        &fs::read_to_string(theirs)?,
+    )?;
+    fs::write(ours, merge.contents)?;
+    if !merge.conflicts.is_empty() {
+        return Err(From::from(format!(
+            "Conflicting ticket fields: {}",
+            merge.conflicts.join(", ")
+        )));
     }
     Ok(())
 }
@@ -67,8 +264,17 @@ pub(super) fn add_new_ticket(
     description: Option<String>,
     status: Option<TicketStatus>,
     ticket_type: Option<TicketType>,
+    priority: Option<TicketPriority>,
+    severity: Option<TicketSeverity>,
+    parent: Option<String>,
 ) -> Result<()> {
     let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let parent = parent.map(TicketId::from);
+    if let Some(parent) = &parent {
+        in_repo_db
+            .get_ticket(parent)
+            .ok_or(format!("Parent ticket {parent} not found."))?;
+    }
     let project_id = get_user_input::get_project_id()?;
 
     let title = title.map_or_else(get_user_input::get_title, |t| Ok(t.into()))?;
@@ -78,16 +284,26 @@ pub(super) fn add_new_ticket(
         Ok,
     )?;
     let ticket_type = ticket_type.map_or_else(get_user_input::get_ticket_type, Ok)?;
+    let priority = priority.map_or_else(get_user_input::get_ticket_priority, Ok)?;
+    let severity = match ticket_type {
+        TicketType::Bug => Some(severity.map_or_else(get_user_input::get_ticket_severity, Ok)?),
+        _ if severity.is_some() => return Err(From::from("Severity only applies to bugs")),
+        _ => None,
+    };
+    let reporter = in_repo_db::current_user().ok();
+    let strategy = in_repo_db::load_settings()?.ids.strategy;
 
     let ticket = Ticket::builder()
-        .id(in_repo_db
-            .get_next_ticket_id()
-            .unwrap_or("T0001".to_owned().into()))
+        .id(in_repo_db.new_ticket_id(project_id.id(), strategy, &BTreeSet::new()))
         .project(project_id)
         .title(title)
         .description(description)
         .status(status)
         .ticket_type(ticket_type)
+        .priority(Some(priority))
+        .severity(severity)
+        .parent(parent)
+        .reporter(reporter)
         .extra(HashMap::new())
         .build();
 
@@ -97,17 +313,30 @@ pub(super) fn add_new_ticket(
     Ok(())
 }
 
-pub(super) fn add_new_project(name: Option<String>, description: Option<String>) -> Result<()> {
+pub(super) fn add_new_project(
+    name: Option<String>,
+    description: Option<String>,
+    key: Option<String>,
+) -> Result<()> {
     println!("Adding a new project");
     let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let key = key.map(|key| key.to_ascii_uppercase());
+    if let Some(key) = &key {
+        ids::validate_key(key)?;
+        if let Some(project) = in_repo_db.find_project(key) {
+            return Err(From::from(format!(
+                "The key {key} is already used by project {}.",
+                project.id()
+            )));
+        }
+    }
     let name = name.map_or_else(get_user_input::get_proj_name, |t| Ok(t.into()))?;
     let description = description.map_or_else(get_user_input::get_proj_desc, |t| Ok(t.into()))?;
     let project = Project::builder()
-        .id(in_repo_db
-            .get_next_project_id()
-            .unwrap_or("P0001".to_owned().into()))
+        .id(in_repo_db.get_next_project_id())
         .name(name)
         .description(description)
+        .key(key)
         .extra(HashMap::new())
         .build();
     println!("{project}");
@@ -115,10 +344,173 @@ pub(super) fn add_new_project(name: Option<String>, description: Option<String>)
     Ok(())
 }
 
+pub(super) fn add_new_milestone(
+    name: Option<String>,
+    description: Option<String>,
+    due: Option<NaiveDate>,
+) -> Result<()> {
+    println!("Adding a new milestone");
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let name = name.map_or_else(get_user_input::get_milestone_name, |t| Ok(t.into()))?;
+    let description =
+        description.map_or_else(get_user_input::get_milestone_desc, |t| Ok(t.into()))?;
+    let milestone = Milestone::builder()
+        .id(in_repo_db.get_next_milestone_id())
+        .name(name)
+        .description(description)
+        .due(due)
+        .status(MilestoneStatus::Open)
+        .extra(HashMap::new())
+        .build();
+    println!("{milestone}");
+    in_repo_db::verify_and_write(&milestone)?;
+    Ok(())
+}
+
+pub(super) fn show_project(id: String) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let project = find_project(&in_repo_db, &id.into())?;
+    let out_string = config.formatter.try_format_single(project)?;
+    println!("{out_string}");
+    Ok(())
+}
+
+pub(super) fn show_milestone(id: String) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let report = in_repo_db
+        .get_milestone_report(&id.into())
+        .ok_or("Milestone not found.")?;
+    let out_string = config.formatter.try_format_single(report)?;
+    println!("{out_string}");
+    Ok(())
+}
+
+pub(super) fn edit_milestone(id: String, status: MilestoneStatus) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let mut milestone = in_repo_db
+        .get_milestone(&id.into())
+        .ok_or("Milestone not found.")?
+        .clone();
+    milestone.set_status(status);
+    in_repo_db::verify_and_write(&milestone)?;
+    println!("{} is now {status}", milestone.id());
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+pub(super) struct TicketEdits {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<TicketStatus>,
+    pub ticket_type: Option<TicketType>,
+    pub priority: Option<TicketPriority>,
+    pub severity: Option<TicketSeverity>,
+    pub parent: Option<String>,
+    pub clear_parent: bool,
+    pub milestone: Option<String>,
+    pub clear_milestone: bool,
+}
+
+impl TicketEdits {
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.description.is_none()
+            && self.status.is_none()
+            && self.ticket_type.is_none()
+            && self.priority.is_none()
+            && self.severity.is_none()
+            && self.parent.is_none()
+            && !self.clear_parent
+            && self.milestone.is_none()
+            && !self.clear_milestone
+    }
+}
+
+pub(super) fn edit_ticket(id: Option<String>, edits: TicketEdits) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let mut ticket = match id {
+        Some(id) => in_repo_db
+            .get_ticket(&id.into())
+            .cloned()
+            .ok_or("Ticket not found.")?,
+        None => get_user_input::select_open_ticket()?,
+    };
+    println!("Editing {}", ticket.id());
+    // With nothing given on the command line, fall back to interactive triage.
+    let interactive = edits.is_empty();
+    let TicketEdits {
+        title,
+        description,
+        status,
+        ticket_type,
+        mut priority,
+        mut severity,
+        parent,
+        clear_parent,
+        milestone,
+        clear_milestone,
+    } = edits;
+    if let Some(title) = title {
+        ticket.set_title(title.into());
+    }
+    if let Some(description) = description {
+        ticket.set_description(description.into());
+    }
+    if let Some(status) = status {
+        ticket.set_status(status);
+    }
+    if let Some(ticket_type) = ticket_type {
+        ticket.set_ticket_type(ticket_type);
+    }
+    if interactive {
+        priority = Some(get_user_input::get_ticket_priority()?);
+        if *ticket.ticket_type() == TicketType::Bug {
+            severity = Some(get_user_input::get_ticket_severity()?);
+        }
+    }
+    if let Some(priority) = priority {
+        ticket.set_priority(priority);
+    }
+    if let Some(severity) = severity {
+        ticket.set_severity(severity)?;
+    }
+    if clear_parent {
+        ticket.set_parent(None);
+    }
+    if let Some(parent) = parent {
+        let parent = TicketId::from(parent);
+        in_repo_db
+            .get_ticket(&parent)
+            .ok_or(format!("Parent ticket {parent} not found."))?;
+        if in_repo_db.would_create_parent_cycle(ticket.id(), &parent) {
+            return Err(From::from(format!(
+                "Making {parent} the parent of {} would create a cycle.",
+                ticket.id()
+            )));
+        }
+        ticket.set_parent(Some(parent));
+    }
+    if clear_milestone {
+        ticket.set_milestone(None);
+    }
+    if let Some(milestone) = milestone {
+        let milestone = MilestoneId::from(milestone);
+        in_repo_db
+            .get_milestone(&milestone)
+            .ok_or(format!("Milestone {milestone} not found."))?;
+        ticket.set_milestone(Some(milestone));
+    }
+    println!("{ticket}");
+    in_repo_db::verify_and_write(&ticket)?;
+    Ok(())
+}
+
 pub(super) fn close_ticket() -> Result<()> {
     println!("Closing a ticket");
     let mut ticket = get_user_input::select_open_ticket()?;
-    ticket.close();
+    ticket.close(None);
 
     in_repo_db::verify_and_write(&ticket)?;
     Ok(())
@@ -133,6 +525,59 @@ pub(super) fn reopen_ticket() -> Result<()> {
     Ok(())
 }
 
+pub(super) fn assign_ticket(id: String, user: &str) -> Result<()> {
+    let mut ticket = get_ticket_by_id(id)?;
+    let user = resolve_user(user)?;
+    println!("Assigning {} to {user}", ticket.id());
+    ticket.assign(user);
+    in_repo_db::verify_and_write(&ticket)?;
+    Ok(())
+}
+
+pub(super) fn unassign_ticket(id: String) -> Result<()> {
+    let mut ticket = get_ticket_by_id(id)?;
+    println!("Unassigning {}", ticket.id());
+    ticket.unassign();
+    in_repo_db::verify_and_write(&ticket)?;
+    Ok(())
+}
+
+pub(super) fn comment_on_ticket(id: String, message: Option<String>) -> Result<()> {
+    let ticket = get_ticket_by_id(id)?;
+    let body = message.map_or_else(get_user_input::get_comment_body, |m| Ok(m.into()))?;
+    if body.to_string().trim().is_empty() {
+        return Err(From::from("Comment cannot be empty."));
+    }
+    let comment = Comment::new(ticket.id().clone(), in_repo_db::current_user().ok(), body);
+    println!("Adding comment {} to {}", comment.id(), ticket.id());
+    in_repo_db::verify_and_write(&comment)?;
+    Ok(())
+}
+
+fn resolve_user(user: &str) -> Result<User> {
+    if user == "me" {
+        return in_repo_db::current_user();
+    }
+    Ok(user.parse()?)
+}
+
+pub(super) fn get_ticket_by_id(id: String) -> Result<Ticket> {
+    ids::validate_id("ticket", &id)?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let ticket = in_repo_db
+        .find_ticket(&id.into())
+        .cloned()
+        .ok_or("Ticket not found.")?;
+    Ok(ticket)
+}
+
+/// Looks a project up by its ID or its key, so `--project API` works as well as `P0002`.
+fn find_project<'a>(in_repo_db: &'a InRepoDB, query: &ProjectId) -> Result<&'a Project> {
+    in_repo_db
+        .find_project(&query.0)
+        .ok_or_else(|| From::from(format!("Project {query} not found.")))
+}
+
 pub(super) fn list_ticket_by_status<S>(list_status: S) -> Result<Vec<Ticket>>
 where
     S: Into<Vec<TicketStatus>>,
@@ -0,0 +1,183 @@
+use crate::config::CONFIG;
+use crate::in_repo_db;
+use crate::in_repo_db::history;
+use crate::in_repo_db::structs::{InRepoDB, Iteration, IterationId, Ticket, TicketId};
+use crate::output_formatter::GenerateOutputFormat;
+use chrono::{Days, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::HashMap;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+const DEFAULT_SPRINT_DAYS: u64 = 14;
+
+pub(crate) fn new_sprint(
+    name: Option<String>,
+    start: Option<NaiveDate>,
+    end: Option<NaiveDate>,
+    capacity: Option<u32>,
+) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let last_end = in_repo_db.iter_iterations().map(Iteration::end).max();
+    let start = start
+        .or_else(|| last_end.and_then(|end| end.checked_add_days(Days::new(1))))
+        .unwrap_or_else(today);
+    let end = end
+        .or_else(|| start.checked_add_days(Days::new(DEFAULT_SPRINT_DAYS - 1)))
+        .ok_or("Invalid sprint end date")?;
+    if end < start {
+        return Err(From::from("A sprint cannot end before it starts."));
+    }
+    let name =
+        name.unwrap_or_else(|| format!("Sprint {}", in_repo_db.iter_iterations().count() + 1));
+    let iteration = Iteration::builder()
+        .id(in_repo_db.get_next_iteration_id())
+        .name(name.into())
+        .start(start)
+        .end(end)
+        .capacity(capacity)
+        .extra(HashMap::new())
+        .build();
+    println!("{iteration}");
+    in_repo_db::verify_and_write(&iteration)?;
+    Ok(())
+}
+
+pub(crate) fn add_to_sprint(tickets: Vec<String>, sprint: Option<String>) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let mut iteration = resolve_sprint(&in_repo_db, sprint)?.clone();
+    for ticket in tickets {
+        let ticket = TicketId::from(ticket);
+        if in_repo_db.get_ticket(&ticket).is_none() {
+            return Err(From::from(format!("Ticket {ticket} not found.")));
+        }
+        if iteration.add_ticket(ticket.clone()) {
+            println!("Added {ticket} to {}", iteration.id());
+        }
+    }
+    if iteration.is_over_capacity() {
+        eprintln!(
+            "Warning: {} now has {} tickets, over its capacity of {}",
+            iteration.id(),
+            iteration.tickets().len(),
+            iteration.capacity().unwrap_or_default()
+        );
+    }
+    in_repo_db::verify_and_write(&iteration)?;
+    Ok(())
+}
+
+pub(crate) fn remove_from_sprint(tickets: Vec<String>, sprint: Option<String>) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let mut iteration = resolve_sprint(&in_repo_db, sprint)?.clone();
+    for ticket in tickets {
+        let ticket = TicketId::from(ticket);
+        if iteration.remove_ticket(&ticket) {
+            println!("Removed {ticket} from {}", iteration.id());
+        }
+    }
+    in_repo_db::verify_and_write(&iteration)?;
+    Ok(())
+}
+
+pub(crate) fn list_sprints() -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let sprints: Vec<&Iteration> = in_repo_db.iter_iterations().collect();
+    let out_string = config.formatter.try_format_multiple(&sprints)?;
+    println!("{out_string}");
+    Ok(())
+}
+
+pub(crate) fn sprint_summary(sprint: Option<String>) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let iteration = resolve_sprint(&in_repo_db, sprint)?;
+    let summary = summarise(&in_repo_db, iteration)?;
+    let out_string = config.formatter.try_format_single(summary)?;
+    println!("{out_string}");
+    Ok(())
+}
+
+/// Copies the unfinished tickets of a sprint (the current one by default) into the sprint that
+/// follows it. The original sprint keeps them so its summary still shows what was committed.
+pub(crate) fn carry_over(from: Option<String>) -> Result<()> {
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let iteration = resolve_sprint(&in_repo_db, from)?;
+    let mut next = in_repo_db
+        .get_next_iteration(iteration.id())
+        .ok_or(format!("No sprint found after {}.", iteration.id()))?
+        .clone();
+    let unfinished = iteration
+        .tickets()
+        .iter()
+        .filter(|id| in_repo_db.get_ticket(id).is_some_and(Ticket::is_open));
+    for ticket in unfinished {
+        if next.add_ticket(ticket.clone()) {
+            println!(
+                "Carried {ticket} over from {} to {}",
+                iteration.id(),
+                next.id()
+            );
+        }
+    }
+    in_repo_db::verify_and_write(&next)?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SprintSummary<'a> {
+    #[serde(flatten)]
+    sprint: &'a Iteration,
+    days_remaining: i64,
+    committed: usize,
+    completed: usize,
+    over_capacity: bool,
+    completed_tickets: Vec<&'a TicketId>,
+    unfinished_tickets: Vec<&'a TicketId>,
+}
+
+fn summarise<'a>(in_repo_db: &'a InRepoDB, iteration: &'a Iteration) -> Result<SprintSummary<'a>> {
+    let mut completed_tickets = Vec::new();
+    let mut unfinished_tickets = Vec::new();
+    let histories = history::ticket_histories()?;
+    for id in iteration.tickets() {
+        let closed_at = in_repo_db.get_ticket(id).and_then(|ticket| {
+            history::closed_at(ticket, histories.get(id).map_or(&[], Vec::as_slice))
+        });
+        let completed_in_sprint = closed_at.is_some_and(|closed_at| {
+            iteration.contains_date(closed_at.with_timezone(&Local).date_naive())
+        });
+        if completed_in_sprint {
+            completed_tickets.push(id);
+        } else {
+            unfinished_tickets.push(id);
+        }
+    }
+    let days_remaining = (iteration.end() - today()).num_days().clamp(-1, i64::MAX) + 1;
+    Ok(SprintSummary {
+        sprint: iteration,
+        days_remaining,
+        committed: iteration.tickets().len(),
+        completed: completed_tickets.len(),
+        over_capacity: iteration.is_over_capacity(),
+        completed_tickets,
+        unfinished_tickets,
+    })
+}
+
+fn resolve_sprint(in_repo_db: &InRepoDB, sprint: Option<String>) -> Result<&Iteration> {
+    match sprint {
+        Some(id) => in_repo_db
+            .get_iteration(&IterationId::from(id))
+            .ok_or_else(|| From::from("Sprint not found.")),
+        None => in_repo_db
+            .get_iteration_on(today())
+            .ok_or_else(|| From::from("No sprint is running today.")),
+    }
+}
+
+pub(super) fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
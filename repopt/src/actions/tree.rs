@@ -0,0 +1,52 @@
+use crate::in_repo_db::structs::{InRepoDB, Ticket, TicketId};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+pub(super) fn render_tree(in_repo_db: &InRepoDB, id: &TicketId) -> Result<String> {
+    let root = in_repo_db.get_ticket(id).ok_or("Ticket not found.")?;
+    let mut out = String::new();
+    writeln!(out, "{}", fmt_node(in_repo_db, root))?;
+    let mut seen = BTreeSet::from([root.id()]);
+    render_children(in_repo_db, root, "", &mut seen, &mut out)?;
+    Ok(out.trim_end().to_owned())
+}
+
+fn render_children<'a>(
+    in_repo_db: &'a InRepoDB,
+    parent: &'a Ticket,
+    prefix: &str,
+    seen: &mut BTreeSet<&'a TicketId>,
+    out: &mut String,
+) -> Result<()> {
+    let children: Vec<&Ticket> = in_repo_db.get_children(parent.id()).collect();
+    for (index, child) in children.iter().enumerate() {
+        let last = index + 1 == children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+        writeln!(out, "{prefix}{branch}{}", fmt_node(in_repo_db, child))?;
+        // A broken parent chain must not send us round in circles.
+        if seen.insert(child.id()) {
+            render_children(in_repo_db, child, &format!("{prefix}{indent}"), seen, out)?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_node(in_repo_db: &InRepoDB, ticket: &Ticket) -> String {
+    let progress = in_repo_db
+        .get_progress(ticket.id())
+        .map(|percent| format!(" ({percent}% done)"))
+        .unwrap_or_default();
+    format!(
+        "{} [{}] {}{progress}",
+        ticket.id(),
+        ticket.status(),
+        ticket.title()
+    )
+}
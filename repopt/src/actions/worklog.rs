@@ -0,0 +1,123 @@
+use super::sprints::today;
+use super::{get_ticket_by_id, resolve_user};
+use crate::config::CONFIG;
+use crate::in_repo_db;
+use crate::in_repo_db::structs::{RunningTimer, WorkDuration, WorkLogEntry};
+use crate::output_formatter::GenerateOutputFormat;
+use chrono::{Datelike, Days, Local, NaiveDate, Utc, Weekday};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+pub(crate) fn start_timer(id: String) -> Result<()> {
+    let ticket = get_ticket_by_id(id)?;
+    if let Some(timer) = in_repo_db::load_timer()? {
+        return Err(From::from(format!(
+            "A timer is already running on {} since {}, stop it first.",
+            timer.ticket,
+            timer.started.with_timezone(&Local).format("%H:%M")
+        )));
+    }
+    in_repo_db::save_timer(Some(&RunningTimer {
+        ticket: ticket.id().clone(),
+        started: Utc::now(),
+    }))?;
+    println!("Started timer on {}", ticket.id());
+    Ok(())
+}
+
+pub(crate) fn stop_timer(note: Option<String>) -> Result<()> {
+    let timer = in_repo_db::load_timer()?.ok_or("No timer is running.")?;
+    let elapsed = Utc::now().signed_duration_since(timer.started);
+    // Round up so a timer stopped straight away still records something.
+    let minutes = u32::try_from((elapsed.num_seconds() + 59) / 60)
+        .unwrap_or_default()
+        .max(1);
+    let date = timer.started.with_timezone(&Local).date_naive();
+    log_work(
+        timer.ticket.to_string(),
+        WorkDuration::from_minutes(minutes),
+        note,
+        date,
+    )?;
+    in_repo_db::save_timer(None)
+}
+
+pub(crate) fn log_time(
+    id: String,
+    duration: WorkDuration,
+    note: Option<String>,
+    date: Option<NaiveDate>,
+) -> Result<()> {
+    log_work(id, duration, note, date.unwrap_or_else(today))
+}
+
+fn log_work(
+    id: String,
+    duration: WorkDuration,
+    note: Option<String>,
+    date: NaiveDate,
+) -> Result<()> {
+    let mut ticket = get_ticket_by_id(id)?;
+    let note = note.filter(|note| !note.trim().is_empty());
+    ticket.log_work(WorkLogEntry::new(
+        in_repo_db::current_user().ok(),
+        date,
+        duration,
+        note,
+    ));
+    in_repo_db::verify_and_write(&ticket)?;
+    println!("Logged {duration} on {}", ticket.id());
+    Ok(())
+}
+
+pub(crate) fn timesheet(since: Option<&str>, author: Option<&str>) -> Result<()> {
+    let config = CONFIG.get().ok_or("Config not initialized")?;
+    let in_repo_db = in_repo_db::collect_in_repo_db()?;
+    let since = since.map(|since| parse_since(since, today())).transpose()?;
+    let author = author.map(resolve_user).transpose()?;
+    let timesheet = in_repo_db.timesheet(since, author.as_ref());
+    println!("{}", config.formatter.try_format_single(&timesheet)?);
+    Ok(())
+}
+
+/// Accepts `today`, `yesterday`, a weekday (meaning the most recent one, today included) or a
+/// `YYYY-MM-DD` date.
+fn parse_since(since: &str, today: NaiveDate) -> Result<NaiveDate> {
+    let since = since.trim().to_lowercase();
+    match since.as_str() {
+        "today" => return Ok(today),
+        "yesterday" => return today.pred_opt().ok_or_else(|| From::from("Invalid date")),
+        _ => {}
+    }
+    if let Ok(weekday) = since.parse::<Weekday>() {
+        let days_back =
+            (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+        return today
+            .checked_sub_days(Days::new(days_back.into()))
+            .ok_or_else(|| From::from("Invalid date"));
+    }
+    NaiveDate::parse_from_str(&since, "%Y-%m-%d").map_err(|_| {
+        From::from(format!(
+            "Invalid date '{since}', expected a weekday, today, yesterday or YYYY-MM-DD"
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_since() {
+        // A Wednesday.
+        let today = NaiveDate::from_ymd_opt(2024, 5, 15).unwrap();
+        let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+        assert_eq!(parse_since("monday", today).unwrap(), date(2024, 5, 13));
+        assert_eq!(parse_since("Wed", today).unwrap(), date(2024, 5, 15));
+        assert_eq!(parse_since("thursday", today).unwrap(), date(2024, 5, 9));
+        assert_eq!(parse_since("yesterday", today).unwrap(), date(2024, 5, 14));
+        assert_eq!(parse_since("2024-01-02", today).unwrap(), date(2024, 1, 2));
+        assert!(parse_since("someday", today).is_err());
+    }
+}
@@ -0,0 +1,238 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::env;
+
+use super::git_utils;
+use super::structs::{TicketId, User};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// A commit whose message mentions a ticket, in the summary, the body or a trailer such as
+/// `Refs: T0001`.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct LinkedCommit {
+    pub commit: String,
+    pub author: User,
+    pub time: DateTime<Utc>,
+    pub summary: String,
+    pub insertions: u32,
+    pub deletions: u32,
+}
+
+/// Every commit reachable from HEAD that references the ticket, newest first.
+pub(crate) fn linked_commits(id: &TicketId) -> Result<Vec<LinkedCommit>> {
+    let cwd = env::current_dir()?;
+    let mentioning: Vec<_> = git_utils::log(&cwd, None)?
+        .into_iter()
+        .filter(|commit| mentions(&commit.message, id))
+        .collect();
+    let ids: Vec<&str> = mentioning
+        .iter()
+        .map(|commit| commit.commit.as_str())
+        .collect();
+    let lines_changed = git_utils::lines_changed(&cwd, &ids)?;
+    Ok(mentioning
+        .into_iter()
+        .zip(lines_changed)
+        .map(|(commit, (insertions, deletions))| LinkedCommit {
+            summary: commit.message.lines().next().unwrap_or_default().to_owned(),
+            commit: commit.commit,
+            author: commit.author,
+            time: commit.time,
+            insertions,
+            deletions,
+        })
+        .collect())
+}
+
+/// A commit whose message closes tickets.
+#[derive(Debug, Clone)]
+pub(crate) struct ClosingCommit {
+    pub commit: String,
+    pub summary: String,
+    pub tickets: Vec<TicketId>,
+}
+
+/// The commits on the current branch after `since` that use a closing keyword, oldest first,
+/// together with the commit HEAD points at (`None` for a repository without commits).
+pub(crate) fn closing_commits(since: Option<&str>) -> Result<(Option<String>, Vec<ClosingCommit>)> {
+    let commits = git_utils::log(env::current_dir()?, since)?;
+    let head = match commits.first() {
+        Some(newest) => Some(newest.commit.clone()),
+        None => git_utils::head_commit(env::current_dir()?)?,
+    };
+    let closing = commits
+        .into_iter()
+        .rev()
+        .filter_map(|commit| {
+            let tickets = closing_references(&commit.message);
+            (!tickets.is_empty()).then(|| ClosingCommit {
+                summary: commit.message.lines().next().unwrap_or_default().to_owned(),
+                commit: commit.commit,
+                tickets,
+            })
+        })
+        .collect();
+    Ok((head, closing))
+}
+
+/// Whether `message` contains the ticket ID as a word of its own, so `T0001` does not match
+/// `T00011` or `XT0001`.
+pub(crate) fn mentions(message: &str, id: &TicketId) -> bool {
+    let id = id.to_string();
+    message.match_indices(&id).any(|(start, _)| {
+        let before = message[..start].chars().next_back();
+        let after = message[start + id.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+/// The words that close the tickets named after them, as in `Fixes T0003` or `Closes: T0003, T0004`.
+const CLOSING_KEYWORDS: [&str; 9] = [
+    "close", "closes", "closed", "fix", "fixes", "fixed", "resolve", "resolves", "resolved",
+];
+
+/// The IDs that follow a closing keyword in `message`. A keyword applies to the run of IDs
+/// after it, which may be separated by commas or `and`; whether they name existing tickets is
+/// left to the caller.
+pub(crate) fn closing_references(message: &str) -> Vec<TicketId> {
+    let mut ids: Vec<TicketId> = Vec::new();
+    let mut after_keyword = false;
+    for word in message.split_whitespace() {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric() && c != '-');
+        if CLOSING_KEYWORDS.contains(&word.to_lowercase().as_str()) {
+            after_keyword = true;
+        } else if after_keyword && looks_like_id(word) {
+            let id = TicketId::from(word);
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        } else if !word.eq_ignore_ascii_case("and") {
+            after_keyword = false;
+        }
+    }
+    ids
+}
+
+/// Every ticket ID mentioned anywhere in `message`, in order of first appearance.
+pub(crate) fn referenced_ids(message: &str) -> Vec<TicketId> {
+    let mut ids: Vec<TicketId> = Vec::new();
+    for id in message
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .flat_map(ids_in_word)
+    {
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    ids
+}
+
+/// The ticket a branch is named after, e.g. `T0001` for `feature/T0001-login-form` or `API-12`
+/// for `API-12-rate-limits`.
+pub(crate) fn branch_ticket(branch: &str) -> Option<TicketId> {
+    branch.split(['/', '_', '.']).flat_map(ids_in_word).next()
+}
+
+/// The IDs in a word whose parts are joined by dashes, where a key like `API` takes the number
+/// after it, as in `API-12-fix`.
+fn ids_in_word(word: &str) -> Vec<TicketId> {
+    let parts: Vec<&str> = word.split('-').collect();
+    let mut ids = Vec::new();
+    let mut index = 0;
+    while index < parts.len() {
+        let part = parts[index];
+        let number = parts.get(index + 1).copied().unwrap_or_default();
+        if looks_like_key(part) && is_number(number) {
+            ids.push(TicketId::from(format!("{part}-{number}")));
+            index += 2;
+            continue;
+        }
+        if looks_like_id(part) {
+            ids.push(TicketId::from(part));
+        }
+        index += 1;
+    }
+    ids
+}
+
+fn looks_like_id(word: &str) -> bool {
+    match word.split_once('-') {
+        Some((key, number)) => looks_like_key(key) && is_number(number),
+        None => word.strip_prefix('T').is_some_and(is_number),
+    }
+}
+
+fn looks_like_key(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+fn is_number(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mentions() {
+        let id = TicketId::from("T0001".to_owned());
+        assert!(mentions("T0001: fix the parser", &id));
+        assert!(mentions("Fix the parser\n\nRefs: T0001", &id));
+        assert!(mentions("Fixes T0001, T0002", &id));
+        assert!(mentions("Fix the parser (T0001)", &id));
+        assert!(!mentions("Fix T00011", &id));
+        assert!(!mentions("Fix XT0001", &id));
+        assert!(!mentions("Fix the parser", &id));
+    }
+
+    #[test]
+    fn test_closing_references() {
+        let ids = |message| -> Vec<String> {
+            closing_references(message)
+                .into_iter()
+                .map(|id| id.to_string())
+                .collect()
+        };
+        assert_eq!(ids("Fixes T0003"), ["T0003"]);
+        assert_eq!(
+            ids("Parser fix\n\nCloses: T0003, T0004 and T0005"),
+            ["T0003", "T0004", "T0005"]
+        );
+        assert_eq!(ids("fixed (T0002). Refs T0006"), ["T0002"]);
+        assert_eq!(ids("Resolves T0001\nCloses T0001"), ["T0001"]);
+        assert!(ids("Refs: T0003").is_empty());
+        assert!(ids("Fix the T0003 parser").is_empty());
+        assert_eq!(ids("Closes API-12 and WEB-3."), ["API-12", "WEB-3"]);
+    }
+
+    #[test]
+    fn test_references_and_branches() {
+        let ids: Vec<String> = referenced_ids("T0002: parse UTF8\n\nRefs: T0001, T0002")
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect();
+        assert_eq!(ids, ["T0002", "T0001"]);
+        let ids: Vec<String> = referenced_ids("API-12: retry on 503 (see WEB-3-T0004)")
+            .into_iter()
+            .map(|id| id.to_string())
+            .collect();
+        assert_eq!(ids, ["API-12", "WEB-3", "T0004"]);
+        assert_eq!(
+            branch_ticket("feature/T0001-login-form"),
+            Some(TicketId::from("T0001"))
+        );
+        assert_eq!(branch_ticket("T12_fix"), Some(TicketId::from("T12")));
+        assert_eq!(
+            branch_ticket("API-12-rate-limits"),
+            Some(TicketId::from("API-12"))
+        );
+        assert_eq!(branch_ticket("fix/utf8-api"), None);
+        assert_eq!(branch_ticket("main"), None);
+    }
+}
@@ -0,0 +1,567 @@
+use clap::ValueEnum;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::structs::{
+    ids, Comment, Iteration, Milestone, MilestoneStatus, Project, Ticket, TicketPriority,
+    TicketSeverity, TicketStatus, TicketType,
+};
+use super::{
+    auto_commit_enabled, get_or_create_irdb_dirs, record_write, remove_file, IRDBPaths,
+    IRDBWritableObject,
+};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum ProblemKind {
+    ParseError,
+    UnknownValue,
+    InvalidId,
+    FileNameMismatch,
+    DuplicateId,
+    MissingProject,
+    UnexpectedFile,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct Problem {
+    /// Relative to the IRDB directory.
+    pub file: PathBuf,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    pub kind: ProblemKind,
+    pub message: String,
+    pub fixed: bool,
+}
+
+/// A file that was read successfully, after any fixes to its values.
+struct Loaded<T> {
+    path: PathBuf,
+    item: T,
+    /// The text of the file, with the fixed values.
+    contents: String,
+    values_fixed: bool,
+}
+
+struct Doctor {
+    paths: IRDBPaths,
+    fix: bool,
+    problems: Vec<Problem>,
+}
+
+/// Reads every file of the database on its own and reports all problems found, instead of
+/// stopping at the first one like loading the database does. With `fix`, files named after the
+/// wrong ID are renamed when the right name is free, and values that only differ from a known
+/// one in case or punctuation, like `status = "in progress"`, are corrected. Fixed files keep
+/// their comments and the order of their keys.
+pub(crate) fn diagnose(fix: bool) -> Result<Vec<Problem>> {
+    diagnose_in(&get_or_create_irdb_dirs()?, fix)
+}
+
+fn diagnose_in(irdb_paths: &IRDBPaths, fix: bool) -> Result<Vec<Problem>> {
+    let mut doctor = Doctor {
+        paths: irdb_paths.clone(),
+        fix,
+        problems: Vec::new(),
+    };
+    let projects: Vec<Loaded<Project>> = doctor.load_dir(&irdb_paths.projects, &[])?;
+    let milestones: Vec<Loaded<Milestone>> = doctor.load_dir(
+        &irdb_paths.milestones,
+        &[("status", variants::<MilestoneStatus>())],
+    )?;
+    let iterations: Vec<Loaded<Iteration>> = doctor.load_dir(&irdb_paths.iterations, &[])?;
+    let tickets: Vec<Loaded<Ticket>> = doctor.load_dir(
+        &irdb_paths.tickets,
+        &[
+            ("status", variants::<TicketStatus>()),
+            ("type", variants::<TicketType>()),
+            ("priority", variants::<TicketPriority>()),
+            ("severity", variants::<TicketSeverity>()),
+        ],
+    )?;
+    let mut comments: Vec<Loaded<Comment>> = Vec::new();
+    for entry in fs::read_dir(&irdb_paths.comments)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            comments.extend(doctor.load_dir(&path, &[])?);
+        } else if !is_hidden(&path) {
+            doctor.report(
+                &path,
+                None,
+                ProblemKind::UnexpectedFile,
+                "comments are kept in a directory per ticket".to_owned(),
+                false,
+            );
+        }
+    }
+
+    let project_ids: BTreeSet<_> = projects.iter().map(|loaded| loaded.item.id()).collect();
+    for loaded in &tickets {
+        let project = loaded.item.get_project_id();
+        if !project_ids.contains(project) {
+            doctor.report(
+                &loaded.path,
+                None,
+                ProblemKind::MissingProject,
+                format!("belongs to project {project}, which does not exist"),
+                false,
+            );
+        }
+    }
+
+    doctor.check_names(projects, |_| Ok(irdb_paths.projects.clone()))?;
+    doctor.check_names(milestones, |_| Ok(irdb_paths.milestones.clone()))?;
+    doctor.check_names(iterations, |_| Ok(irdb_paths.iterations.clone()))?;
+    doctor.check_names(tickets, |_| Ok(irdb_paths.tickets.clone()))?;
+    // The ticket becomes part of the path, so it has to be checked like an ID.
+    doctor.check_names(comments, |comment| {
+        ids::validate_id("ticket", &comment.ticket().0)?;
+        Ok(irdb_paths.comments.join(comment.ticket().to_string()))
+    })?;
+    Ok(doctor.problems)
+}
+
+impl Doctor {
+    fn report(
+        &mut self,
+        path: &Path,
+        line: Option<usize>,
+        kind: ProblemKind,
+        message: String,
+        fixed: bool,
+    ) {
+        self.problems.push(Problem {
+            file: path
+                .strip_prefix(&self.paths.base)
+                .unwrap_or(path)
+                .to_path_buf(),
+            line,
+            kind,
+            message,
+            fixed,
+        });
+    }
+
+    /// Parses the TOML files in `dir`, reporting the ones that cannot be read and any other
+    /// files. `enum_fields` lists the fields limited to a set of values, with those values.
+    fn load_dir<T: DeserializeOwned>(
+        &mut self,
+        dir: &Path,
+        enum_fields: &[(&str, Vec<String>)],
+    ) -> Result<Vec<Loaded<T>>> {
+        let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<_>>()?;
+        paths.sort();
+        let mut loaded = Vec::new();
+        for path in paths {
+            if is_hidden(&path) {
+                continue;
+            }
+            if path.is_dir() || path.extension().and_then(|ext| ext.to_str()) != Some("toml") {
+                let what = if path.is_dir() { "directory" } else { "file" };
+                self.report(
+                    &path,
+                    None,
+                    ProblemKind::UnexpectedFile,
+                    format!("not a TOML {what}, so it is ignored"),
+                    false,
+                );
+                continue;
+            }
+            if let Some(item) = self.load_file(&path, enum_fields) {
+                loaded.push(item);
+            }
+        }
+        Ok(loaded)
+    }
+
+    fn load_file<T: DeserializeOwned>(
+        &mut self,
+        path: &Path,
+        enum_fields: &[(&str, Vec<String>)],
+    ) -> Option<Loaded<T>> {
+        let Ok(contents) = fs::read_to_string(path) else {
+            self.report(
+                path,
+                None,
+                ProblemKind::ParseError,
+                "not valid UTF-8".to_owned(),
+                false,
+            );
+            return None;
+        };
+        let table: toml::Table = match contents.parse() {
+            Ok(table) => table,
+            Err(err) => {
+                let line = err.span().map(|span| line_of(&contents, span.start));
+                self.report(
+                    path,
+                    line,
+                    ProblemKind::ParseError,
+                    err.message().to_owned(),
+                    false,
+                );
+                return None;
+            }
+        };
+        let mut unfixable = false;
+        let mut corrections = Vec::new();
+        for (field, names) in enum_fields {
+            let Some(toml::Value::String(value)) = table.get(*field) else {
+                continue;
+            };
+            if names.contains(value) {
+                continue;
+            }
+            let line = find_line(&contents, field);
+            let correction = canonical_value(value, names);
+            let fixed = self.fix && correction.is_some();
+            let problem = match correction {
+                Some(name) => format!("{field} \"{value}\" should be \"{name}\""),
+                None => format!(
+                    "unknown {field} \"{value}\", expected one of {}",
+                    names.join(", ")
+                ),
+            };
+            self.report(path, line, ProblemKind::UnknownValue, problem, fixed);
+            match correction {
+                Some(name) if self.fix => corrections.push((*field, name)),
+                _ => unfixable = true,
+            }
+        }
+        if unfixable {
+            return None;
+        }
+        let values_fixed = !corrections.is_empty();
+        let contents = if values_fixed {
+            correct_values(&contents, &corrections)
+        } else {
+            contents
+        };
+        let item = toml::from_str(&contents).map_err(|err: toml::de::Error| {
+            let line = err.span().map(|span| line_of(&contents, span.start));
+            (err, line)
+        });
+        match item {
+            Ok(item) => Some(Loaded {
+                path: path.to_path_buf(),
+                item,
+                contents,
+                values_fixed,
+            }),
+            Err((err, line)) => {
+                self.report(
+                    path,
+                    line,
+                    ProblemKind::ParseError,
+                    err.message().to_owned(),
+                    false,
+                );
+                None
+            }
+        }
+    }
+
+    /// Checks that every file is named after the ID it declares and that no two files declare
+    /// the same one. Files named after the wrong ID are moved when the right name is free.
+    /// `dir_of` gives the directory an item belongs in, without creating it.
+    fn check_names<T: IRDBWritableObject>(
+        &mut self,
+        loaded: Vec<Loaded<T>>,
+        dir_of: impl Fn(&T) -> Result<PathBuf>,
+    ) -> Result<()> {
+        let mut by_path: BTreeMap<PathBuf, Vec<Loaded<T>>> = BTreeMap::new();
+        for item in loaded {
+            let id = item.item.fmt_stub();
+            let dir = ids::validate_id("file", &id).and_then(|()| dir_of(&item.item));
+            match dir {
+                Ok(dir) => by_path
+                    .entry(dir.join(format!("{id}.toml")))
+                    .or_default()
+                    .push(item),
+                Err(err) => self.report(
+                    &item.path,
+                    None,
+                    ProblemKind::InvalidId,
+                    err.to_string(),
+                    false,
+                ),
+            }
+        }
+        for (expected, mut files) in by_path {
+            // The file already at the right name keeps it.
+            files.sort_by_key(|file| file.path != expected);
+            let mut files = files.into_iter();
+            let Some(first) = files.next() else {
+                continue;
+            };
+            let name = file_name(&expected);
+            if first.path == expected {
+                if first.values_fixed {
+                    rewrite(&first.path, &first)?;
+                }
+            } else if expected.exists() {
+                // Whatever is there did not load as this ID, e.g. it does not parse, and is
+                // not to be overwritten.
+                if first.values_fixed {
+                    rewrite(&first.path, &first)?;
+                }
+                self.report(
+                    &first.path,
+                    None,
+                    ProblemKind::FileNameMismatch,
+                    format!(
+                        "declares {}, but {name} is taken by a file that does not, so it cannot be \
+                         moved there",
+                        first.item.fmt_stub()
+                    ),
+                    false,
+                );
+            } else if self.fix {
+                if let Some(dir) = expected.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                rewrite(&expected, &first)?;
+                remove_file(&first.path, first.item.describe())?;
+                self.report(
+                    &first.path,
+                    None,
+                    ProblemKind::FileNameMismatch,
+                    format!("moved to {name}, after the ID it declares"),
+                    true,
+                );
+            } else {
+                if first.values_fixed {
+                    rewrite(&first.path, &first)?;
+                }
+                self.report(
+                    &first.path,
+                    None,
+                    ProblemKind::FileNameMismatch,
+                    format!(
+                        "declares {}, so it should be named {name}",
+                        first.item.fmt_stub()
+                    ),
+                    false,
+                );
+            }
+            for duplicate in files {
+                self.report(
+                    &duplicate.path,
+                    None,
+                    ProblemKind::DuplicateId,
+                    format!(
+                        "declares {}, which {} already uses; run `repopt renumber` for tickets",
+                        duplicate.item.fmt_stub(),
+                        file_name(&first.path)
+                    ),
+                    false,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes the text of a `loaded` file, with any fixes, to `path`.
+fn rewrite<T: IRDBWritableObject>(path: &Path, loaded: &Loaded<T>) -> Result<()> {
+    if auto_commit_enabled()? {
+        record_write(path, loaded.item.describe())?;
+    }
+    fs::write(path, &loaded.contents)?;
+    Ok(())
+}
+
+/// `contents` with the top-level `field`s set to their corrected names. Only the values change,
+/// so comments, the order of the keys and the spacing stay as they were.
+fn correct_values(contents: &str, corrections: &[(&str, &str)]) -> String {
+    // Already parsed as a table, so this cannot fail.
+    let Ok(mut document) = contents.parse::<toml_edit::DocumentMut>() else {
+        return contents.to_owned();
+    };
+    for (field, name) in corrections {
+        if let Some(value) = document
+            .get_mut(field)
+            .and_then(toml_edit::Item::as_value_mut)
+        {
+            let decor = value.decor().clone();
+            *value = (*name).into();
+            *value.decor_mut() = decor;
+        }
+    }
+    document.to_string()
+}
+
+/// The names a field of type `T` takes in the files.
+fn variants<T: ValueEnum + Serialize>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|variant| match serde_json::to_value(variant) {
+            Ok(serde_json::Value::String(name)) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The one name in `names` that `value` matches when case and punctuation are ignored.
+fn canonical_value<'a>(value: &str, names: &'a [String]) -> Option<&'a str> {
+    let squash = |text: &str| -> String {
+        text.chars()
+            .filter(char::is_ascii_alphanumeric)
+            .map(|c| c.to_ascii_lowercase())
+            .collect()
+    };
+    let value = squash(value);
+    let mut matches = names.iter().filter(|name| squash(name) == value);
+    match (matches.next(), matches.next()) {
+        (Some(name), None) => Some(name),
+        _ => None,
+    }
+}
+
+fn line_of(contents: &str, offset: usize) -> usize {
+    contents[..offset.min(contents.len())].matches('\n').count() + 1
+}
+
+/// The line a top-level `field` is set on, for pointing at values the TOML parser accepted.
+fn find_line(contents: &str, field: &str) -> Option<usize> {
+    contents
+        .lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(field)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .map(|index| index + 1)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map_or_else(String::new, |name| name.to_string_lossy().into_owned())
+}
+
+/// Dot files like `.gitkeep` are left alone.
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_repo_db::create_irdb_dirs;
+    use crate::in_repo_db::structs::tests::ticket_fixture;
+    use crate::in_repo_db::test_utils::TempDir;
+
+    #[test]
+    fn test_canonical_value() {
+        let statuses = variants::<TicketStatus>();
+        assert_eq!(statuses, ["Backlog", "InProgress", "Closed"]);
+        assert_eq!(
+            canonical_value("in progress", &statuses),
+            Some("InProgress")
+        );
+        assert_eq!(canonical_value("BACKLOG", &statuses), Some("Backlog"));
+        assert_eq!(canonical_value("Done", &statuses), None);
+        assert_eq!(
+            canonical_value("p1", &variants::<TicketPriority>()),
+            Some("P1")
+        );
+    }
+
+    #[test]
+    fn test_lines() {
+        let contents = "id = \"T0001\"\n\nstatus = \"Done\"\n[project]\nstatus = \"x\"\n";
+        assert_eq!(find_line(contents, "status"), Some(3));
+        assert_eq!(find_line(contents, "title"), None);
+        assert_eq!(line_of(contents, contents.find("[project]").unwrap()), 4);
+    }
+
+    #[test]
+    fn test_fix_never_overwrites_an_unreadable_file() {
+        let dir = TempDir::new("doctor");
+        let paths = create_irdb_dirs(dir.path().join(".irdb")).unwrap();
+        let malformed = "id = \"T0005\"\ntitle = \n";
+        fs::write(paths.tickets.join("T0005.toml"), malformed).unwrap();
+        let stale = ticket_fixture("T0005", "Stale copy", TicketStatus::Backlog);
+        fs::write(
+            paths.tickets.join("T0005-old.toml"),
+            toml::to_string(&stale).unwrap(),
+        )
+        .unwrap();
+
+        let problems = diagnose_in(&paths, true).unwrap();
+        assert_eq!(
+            fs::read_to_string(paths.tickets.join("T0005.toml")).unwrap(),
+            malformed
+        );
+        assert!(paths.tickets.join("T0005-old.toml").exists());
+        let mismatch = problems
+            .iter()
+            .find(|problem| problem.kind == ProblemKind::FileNameMismatch)
+            .unwrap();
+        assert_eq!(mismatch.file, Path::new("tickets/T0005-old.toml"));
+        assert!(!mismatch.fixed);
+        assert!(problems
+            .iter()
+            .any(|problem| problem.kind == ProblemKind::ParseError));
+    }
+
+    #[test]
+    fn test_fix_keeps_the_rest_of_the_file() {
+        let dir = TempDir::new("doctor");
+        let paths = create_irdb_dirs(dir.path().join(".irdb")).unwrap();
+        let file = |id: &str| {
+            format!(
+                "# Imported from the old tracker\nid = \"{id}\"\nstatus = \"in progress\"  # was WIP\n\
+                 title = \"Fix login\"\ndescription = \"\"\ntype = \"Bug\"\n\n[project]\nid = \"P0001\"\n"
+            )
+        };
+        fs::write(paths.tickets.join("T0001.toml"), file("T0001")).unwrap();
+        fs::write(paths.tickets.join("T0002-old.toml"), file("T0002")).unwrap();
+
+        let problems = diagnose_in(&paths, true).unwrap();
+        let fixed = problems.iter().filter(|problem| problem.fixed).count();
+        // The status of both files, and the name of the second.
+        assert_eq!(fixed, 3);
+        for id in ["T0001", "T0002"] {
+            assert_eq!(
+                fs::read_to_string(paths.tickets.join(format!("{id}.toml"))).unwrap(),
+                file(id).replace("\"in progress\"", "\"InProgress\"")
+            );
+        }
+        assert!(!paths.tickets.join("T0002-old.toml").exists());
+    }
+
+    #[test]
+    fn test_comments_are_checked_without_creating_directories() {
+        let dir = TempDir::new("doctor");
+        let paths = create_irdb_dirs(dir.path().join(".irdb")).unwrap();
+        let comment_dir = paths.comments.join("T0001");
+        fs::create_dir(&comment_dir).unwrap();
+        for (ticket, body) in [("T0002", "Misplaced"), ("../x", "Escaping")] {
+            let comment = Comment::new(ticket.into(), None, body.into());
+            fs::write(
+                comment_dir.join(format!("{}.toml", comment.id())),
+                toml::to_string(&comment).unwrap(),
+            )
+            .unwrap();
+        }
+
+        let problems = diagnose_in(&paths, false).unwrap();
+        assert!(!paths.comments.join("T0002").exists());
+        assert!(!paths.base.join("x").exists());
+        let kinds: Vec<ProblemKind> = problems.iter().map(|problem| problem.kind).collect();
+        assert_eq!(
+            kinds,
+            [ProblemKind::InvalidId, ProblemKind::FileNameMismatch]
+        );
+    }
+}
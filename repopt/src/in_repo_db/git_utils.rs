@@ -1,4 +1,16 @@
+use chrono::{DateTime, Utc};
+use gix::bstr::{BStr, BString, ByteSlice};
+use gix::index::entry::{Flags, Mode, Stage, Stat};
+use gix::objs::tree::EntryMode;
+use gix::traverse::commit::simple::Sorting;
+use gix::ObjectId;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use super::structs::User;
 
 type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
 type Result<T> = std::result::Result<T, Error>;
@@ -13,13 +25,1062 @@ pub(super) fn find_git_root<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
         .canonicalize()?)
 }
 
+pub(super) fn find_git_dir<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    Ok(gix::discover(path)?.git_dir().to_path_buf())
+}
+
+pub(super) fn get_git_user<P: AsRef<Path>>(path: P) -> Result<User> {
+    let repo = gix::discover(path)?;
+    let config = repo.config_snapshot();
+    let name = config
+        .string("user.name")
+        .ok_or("Git user.name is not configured")?
+        .to_string();
+    let email = config.string("user.email").map(|email| email.to_string());
+    Ok(User::new(name, email))
+}
+
+/// One committed version of a file, as seen by a commit that changed it.
+#[derive(Debug, Clone)]
+pub(crate) struct FileRevision {
+    pub commit: String,
+    pub author: User,
+    pub time: DateTime<Utc>,
+    pub summary: String,
+    /// `None` when the commit deleted the file.
+    pub contents: Option<String>,
+}
+
+/// Walks the history of HEAD, newest first, returning every commit that changed `path`.
+/// Like `git log -- <path>`, merges that took the file unchanged from one side are skipped.
+pub(super) fn file_history<P: AsRef<Path>>(path: P) -> Result<Vec<FileRevision>> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or("Invalid file path")?;
+    let mut history = dir_history(path.parent().ok_or("Invalid file path")?, |name| {
+        name == file_name
+    })?;
+    Ok(history.remove(file_name).unwrap_or_default())
+}
+
+/// A commit that changed a file, and the blob it left there.
+type FileChange = (Rc<FileRevision>, Option<ObjectId>);
+
+/// Like [`file_history`] for every file directly in `dir` that `include` accepts, walking the
+/// history only once. Files are keyed by name.
+pub(super) fn dir_history<P: AsRef<Path>>(
+    dir: P,
+    include: impl Fn(&str) -> bool,
+) -> Result<BTreeMap<String, Vec<FileRevision>>> {
+    let repo = gix::discover(dir.as_ref())?;
+    let relative_dir = repo_relative_dir(&repo, dir.as_ref())?;
+    let Some(head) = repo.head()?.id() else {
+        return Ok(BTreeMap::new());
+    };
+    let mut buf = Vec::new();
+    let mut listings = HashMap::new();
+    let mut by_name: BTreeMap<String, Vec<FileChange>> = BTreeMap::new();
+    for info in repo
+        .rev_walk([head])
+        .sorting(Sorting::ByCommitTimeNewestFirst)
+        .all()?
+    {
+        let commit = info?.object()?;
+        let tree = dir_tree(&commit, &relative_dir, &mut buf)?;
+        let parent_trees = commit
+            .parent_ids()
+            .map(|parent| dir_tree(&parent.object()?.into_commit(), &relative_dir, &mut buf))
+            .collect::<Result<Vec<_>>>()?;
+        // Whatever the commit took unchanged from a parent, it did not change.
+        if parent_trees.contains(&tree) {
+            continue;
+        }
+        let blobs = dir_listing(&repo, tree, &include, &mut listings)?;
+        let parent_blobs = parent_trees
+            .into_iter()
+            .map(|parent_tree| dir_listing(&repo, parent_tree, &include, &mut listings))
+            .collect::<Result<Vec<_>>>()?;
+        let names: BTreeSet<&String> = blobs
+            .keys()
+            .chain(parent_blobs.iter().flat_map(|parent| parent.keys()))
+            .collect();
+        let author = commit.author()?;
+        let revision = Rc::new(FileRevision {
+            commit: commit.id().to_string(),
+            author: User::new(author.name.to_string(), Some(author.email.to_string())),
+            time: DateTime::from_timestamp(commit.time()?.seconds, 0).unwrap_or_default(),
+            summary: commit.message()?.summary().to_string(),
+            contents: None,
+        });
+        for name in names {
+            let blob = blobs.get(name).copied();
+            let changed = if parent_blobs.is_empty() {
+                blob.is_some()
+            } else {
+                parent_blobs
+                    .iter()
+                    .all(|parent| parent.get(name).copied() != blob)
+            };
+            if changed {
+                by_name
+                    .entry(name.clone())
+                    .or_default()
+                    .push((revision.clone(), blob));
+            }
+        }
+    }
+    by_name
+        .into_iter()
+        .map(|(name, revisions)| {
+            let revisions = revisions
+                .into_iter()
+                .map(|(revision, blob)| {
+                    let contents = match blob {
+                        Some(id) => Some(String::from_utf8(repo.find_object(id)?.detach().data)?),
+                        None => None,
+                    };
+                    Ok(FileRevision {
+                        contents,
+                        ..(*revision).clone()
+                    })
+                })
+                .collect::<Result<_>>()?;
+            Ok((name, revisions))
+        })
+        .collect()
+}
+
+/// The tree at `dir` in `commit`, `None` if there is none.
+fn dir_tree(commit: &gix::Commit<'_>, dir: &Path, buf: &mut Vec<u8>) -> Result<Option<ObjectId>> {
+    let tree = commit.tree()?;
+    if dir.as_os_str().is_empty() {
+        return Ok(Some(tree.id));
+    }
+    let entry = tree.lookup_entry_by_path(dir, buf)?;
+    Ok(entry
+        .filter(|entry| entry.mode().is_tree())
+        .map(|entry| entry.object_id()))
+}
+
+/// The files in `tree` that `include` accepts, by name. Names that are not UTF-8 are skipped. Listings are cached by tree, as most
+/// commits leave the directory as their parent had it.
+fn dir_listing(
+    repo: &gix::Repository,
+    tree: Option<ObjectId>,
+    include: impl Fn(&str) -> bool,
+    listings: &mut HashMap<Option<ObjectId>, Rc<BTreeMap<String, ObjectId>>>,
+) -> Result<Rc<BTreeMap<String, ObjectId>>> {
+    if let Some(listing) = listings.get(&tree) {
+        return Ok(listing.clone());
+    }
+    let mut listing = BTreeMap::new();
+    if let Some(tree) = tree {
+        for entry in repo.find_object(tree)?.into_tree().iter() {
+            let entry = entry?;
+            let Ok(name) = entry.filename().to_str() else {
+                continue;
+            };
+            if entry.mode().is_blob() && include(name) {
+                listing.insert(name.to_owned(), entry.oid().to_owned());
+            }
+        }
+    }
+    let listing = Rc::new(listing);
+    listings.insert(tree, listing.clone());
+    Ok(listing)
+}
+
+/// The contents of `path` as of `revision` (anything `git rev-parse` accepts), or `None` if the
+/// file did not exist in that commit.
+pub(super) fn file_at_revision<P: AsRef<Path>>(path: P, revision: &str) -> Result<Option<String>> {
+    let path = path.as_ref();
+    let repo = gix::discover(path.parent().ok_or("Invalid file path")?)?;
+    let relative_path = repo_relative_path(&repo, path)?;
+    let commit = repo
+        .rev_parse_single(revision)
+        .map_err(|_| format!("Unknown revision {revision}"))?
+        .object()?
+        .peel_to_kind(gix::object::Kind::Commit)?
+        .try_into_commit()?;
+    let mut buf = Vec::new();
+    match blob_at(&commit, &relative_path, &mut buf)? {
+        Some(id) => Ok(Some(String::from_utf8(
+            repo.find_object(id)?.detach().data,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+/// A commit as listed by [`log`].
+#[derive(Debug, Clone)]
+pub(crate) struct CommitInfo {
+    pub commit: String,
+    pub author: User,
+    pub time: DateTime<Utc>,
+    pub message: String,
+}
+
+/// The short name of the checked out branch, `None` on a detached HEAD.
+pub(super) fn current_branch<P: AsRef<Path>>(path: P) -> Result<Option<String>> {
+    let repo = gix::discover(path)?;
+    Ok(repo.head_name()?.map(|name| name.shorten().to_string()))
+}
+
+/// Fails unless `branch` can be checked out by `create_and_checkout_branch`, without
+/// changing anything.
+pub(super) fn check_new_branch<P: AsRef<Path>>(path: P, branch: &str) -> Result<()> {
+    new_branch_ref(&gix::discover(path)?, branch)?;
+    Ok(())
+}
+
+/// Creates `branch` at HEAD and checks it out, like `git checkout -b`. As the new branch
+/// points at the commit already checked out, the index and working tree stay as they are.
+/// An existing branch is only switched to when it points at HEAD too.
+pub(super) fn create_and_checkout_branch<P: AsRef<Path>>(path: P, branch: &str) -> Result<()> {
+    use gix::refs::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+    use gix::refs::Target;
+
+    let repo = gix::discover(path)?;
+    let (name, exists) = new_branch_ref(&repo, branch)?;
+    let head = repo.head()?;
+    let previous = head
+        .referent_name()
+        .map_or_else(|| "HEAD".to_owned(), |name| name.shorten().to_string());
+    if let Some(head_id) = head.id() {
+        if !exists {
+            repo.reference(
+                name.clone(),
+                head_id.detach(),
+                PreviousValue::MustNotExist,
+                format!("branch: Created from {previous}"),
+            )?;
+        }
+    }
+    repo.edit_reference(RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: format!("checkout: moving from {previous} to {branch}").into(),
+            },
+            expected: PreviousValue::Any,
+            new: Target::Symbolic(name),
+        },
+        name: "HEAD".try_into()?,
+        deref: false,
+    })?;
+    Ok(())
+}
+
+/// The reference `branch` is stored under, and whether it already exists at HEAD.
+fn new_branch_ref(repo: &gix::Repository, branch: &str) -> Result<(gix::refs::FullName, bool)> {
+    let name = gix::refs::FullName::try_from(format!("refs/heads/{branch}"))
+        .map_err(|_| format!("Invalid branch name '{branch}'"))?;
+    let Some(mut existing) = repo.try_find_reference(&name)? else {
+        return Ok((name, false));
+    };
+    if Some(existing.peel_to_id_in_place()?) != repo.head()?.id() {
+        return Err(From::from(format!(
+            "Branch {branch} already exists on another commit, check it out with git."
+        )));
+    }
+    Ok((name, true))
+}
+
+/// Sets `merge.<driver>.name` and `merge.<driver>.driver` in the repository's own config
+/// file, leaving the rest of it as it was.
+pub(super) fn register_merge_driver<P: AsRef<Path>>(
+    path: P,
+    driver: &str,
+    name: &str,
+    command: &str,
+) -> Result<()> {
+    let repo = gix::discover(path)?;
+    let config_path = repo.common_dir().join("config");
+    let mut config =
+        gix::config::File::from_path_no_includes(config_path.clone(), gix::config::Source::Local)?;
+    config.set_raw_value_by("merge", Some(driver.into()), "name", name)?;
+    config.set_raw_value_by("merge", Some(driver.into()), "driver", command)?;
+    let mut out = Vec::new();
+    config.write_to(&mut out)?;
+    fs::write(config_path, out)?;
+    Ok(())
+}
+
+/// Where git looks for hooks: `core.hooksPath` when it is set, `.git/hooks` otherwise.
+pub(super) fn hooks_dir<P: AsRef<Path>>(path: P) -> Result<PathBuf> {
+    let repo = gix::discover(path)?;
+    let configured = repo
+        .config_snapshot()
+        .string("core.hooksPath")
+        .map(|hooks_path| PathBuf::from(hooks_path.to_string()));
+    Ok(match (configured, repo.work_dir()) {
+        (Some(hooks_path), _) if hooks_path.is_absolute() => hooks_path,
+        (Some(hooks_path), Some(work_dir)) => work_dir.join(hooks_path),
+        _ => repo.common_dir().join("hooks"),
+    })
+}
+
+/// The commit HEAD points at, if there is one yet.
+pub(super) fn head_commit<P: AsRef<Path>>(path: P) -> Result<Option<String>> {
+    let repo = gix::discover(path)?;
+    Ok(repo.head()?.id().map(|id| id.to_string()))
+}
+
+/// The first parent of `revision`, or `None` for a root commit.
+pub(super) fn first_parent<P: AsRef<Path>>(path: P, revision: &str) -> Result<Option<String>> {
+    let repo = gix::discover(path)?;
+    let commit = repo.rev_parse_single(revision)?.object()?.into_commit();
+    let parent = commit.parent_ids().next().map(|id| id.to_string());
+    Ok(parent)
+}
+
+/// Every commit reachable from HEAD, newest first. With `since`, commits reachable from that
+/// revision are left out, like `git log <since>..HEAD`.
+pub(super) fn log<P: AsRef<Path>>(path: P, since: Option<&str>) -> Result<Vec<CommitInfo>> {
+    let repo = gix::discover(path)?;
+    let Some(head) = repo.head()?.id() else {
+        return Ok(Vec::new());
+    };
+    let mut hidden = BTreeSet::new();
+    if let Some(since) = since {
+        let since = repo
+            .rev_parse_single(since)
+            .map_err(|_| format!("Unknown revision {since}"))?;
+        for info in repo.rev_walk([since]).all()? {
+            hidden.insert(info?.id);
+        }
+    }
+    let mut commits = Vec::new();
+    for info in repo
+        .rev_walk([head])
+        .sorting(Sorting::ByCommitTimeNewestFirst)
+        .selected(|id| !hidden.contains(id))?
+    {
+        let commit = info?.object()?;
+        let author = commit.author()?;
+        commits.push(CommitInfo {
+            commit: commit.id().to_string(),
+            author: User::new(author.name.to_string(), Some(author.email.to_string())),
+            time: DateTime::from_timestamp(commit.time()?.seconds, 0).unwrap_or_default(),
+            message: commit.message_raw()?.to_string(),
+        });
+    }
+    Ok(commits)
+}
+
+/// Lines added and removed by each of `commits` compared to its first parent, across all
+/// files.
+pub(super) fn lines_changed<P: AsRef<Path>>(path: P, commits: &[&str]) -> Result<Vec<(u32, u32)>> {
+    let repo = gix::discover(path)?;
+    commits
+        .iter()
+        .map(|commit| {
+            let commit = repo
+                .rev_parse_single(*commit)?
+                .object()?
+                .try_into_commit()?;
+            let parent_tree = match commit.parent_ids().next() {
+                Some(parent) => Some(parent.object()?.into_commit().tree_id()?.detach()),
+                None => None,
+            };
+            let mut changed = Vec::new();
+            changed_blobs(
+                &repo,
+                parent_tree,
+                Some(commit.tree_id()?.detach()),
+                &mut changed,
+            )?;
+            let blob = |id: Option<ObjectId>| -> Result<Vec<u8>> {
+                match id {
+                    Some(id) => Ok(repo.find_object(id)?.detach().data),
+                    None => Ok(Vec::new()),
+                }
+            };
+            let (mut insertions, mut deletions) = (0, 0);
+            for (old, new) in changed {
+                let (old, new) = (blob(old)?, blob(new)?);
+                let input = imara_diff::intern::InternedInput::new(
+                    imara_diff::sources::byte_lines(&old),
+                    imara_diff::sources::byte_lines(&new),
+                );
+                let counter = imara_diff::diff(
+                    imara_diff::Algorithm::Histogram,
+                    &input,
+                    imara_diff::sink::Counter::default(),
+                );
+                insertions += counter.insertions;
+                deletions += counter.removals;
+            }
+            Ok((insertions, deletions))
+        })
+        .collect()
+}
+
+/// Collects the blobs that differ between two trees as `(old, new)` pairs, only descending
+/// into subtrees that changed. Submodules are left out.
+fn changed_blobs(
+    repo: &gix::Repository,
+    old: Option<ObjectId>,
+    new: Option<ObjectId>,
+    changed: &mut Vec<(Option<ObjectId>, Option<ObjectId>)>,
+) -> Result<()> {
+    if old == new {
+        return Ok(());
+    }
+    let entries = |tree: Option<ObjectId>| -> Result<BTreeMap<BString, (ObjectId, EntryMode)>> {
+        let Some(tree) = tree else {
+            return Ok(BTreeMap::new());
+        };
+        repo.find_object(tree)?
+            .into_tree()
+            .iter()
+            .map(|entry| {
+                let entry = entry?;
+                Ok((
+                    entry.filename().to_owned(),
+                    (entry.oid().to_owned(), entry.mode()),
+                ))
+            })
+            .collect()
+    };
+    let (old, new) = (entries(old)?, entries(new)?);
+    let names: BTreeSet<&BString> = old.keys().chain(new.keys()).collect();
+    for name in names {
+        let (old, new) = (old.get(name), new.get(name));
+        if old == new {
+            continue;
+        }
+        let of_kind = |entry: Option<&(ObjectId, EntryMode)>, is_kind: fn(&EntryMode) -> bool| {
+            entry.filter(|(_, mode)| is_kind(mode)).map(|(id, _)| *id)
+        };
+        // An entry can change kind, say from a file to a directory, so look at both.
+        changed_blobs(
+            repo,
+            of_kind(old, EntryMode::is_tree),
+            of_kind(new, EntryMode::is_tree),
+            changed,
+        )?;
+        let blobs = (
+            of_kind(old, EntryMode::is_blob_or_symlink),
+            of_kind(new, EntryMode::is_blob_or_symlink),
+        );
+        if blobs.0 != blobs.1 {
+            changed.push(blobs);
+        }
+    }
+    Ok(())
+}
+
+/// Files whose staged version differs from HEAD, relative to the repository root. Conflicts and
+/// intent-to-add entries count as staged too.
+pub(super) fn staged_paths<P: AsRef<Path>>(path: P) -> Result<BTreeSet<BString>> {
+    let repo = gix::discover(path)?;
+    let mut head = head_files(&repo)?;
+    let index = repo.index_or_empty()?;
+    let mut staged = BTreeSet::new();
+    for entry in index.entries() {
+        let entry_path = entry.path(&index).to_owned();
+        let committed = head.remove(&entry_path);
+        let unchanged = entry.stage() == Stage::Unconflicted
+            && !entry.flags.contains(Flags::INTENT_TO_ADD)
+            && committed == Some((entry.id, entry.mode.to_tree_entry_mode()));
+        if !unchanged {
+            staged.insert(entry_path);
+        }
+    }
+    // Whatever is left in HEAD but not in the index is a staged deletion.
+    staged.extend(head.into_keys());
+    Ok(staged)
+}
+
+/// Stages exactly `paths` (removing those that no longer exist) and commits them on top of
+/// HEAD with `message`, returning the new commit ID. The index is updated so the working tree
+/// is clean afterwards.
+pub(super) fn commit_files(paths: &[PathBuf], message: &str) -> Result<String> {
+    let first = paths.first().ok_or("Nothing to commit")?;
+    let repo = gix::discover(first.parent().ok_or("Invalid file path")?)?;
+    // Taken before the commit, so HEAD only moves once the index is sure to follow.
+    let lock = lock_index(&repo)?;
+    let mut index: gix::index::File = (**repo.index_or_empty()?).clone();
+    let changed = update_index(&repo, &mut index, paths)?;
+    let files = index
+        .entries()
+        .iter()
+        .filter_map(|entry| {
+            let mode = entry.mode.to_tree_entry_mode()?;
+            Some((entry.path(&index).to_owned(), (entry.id, mode)))
+        })
+        .collect();
+    let tree = write_tree(&repo, files)?;
+    let parents: Vec<ObjectId> = repo
+        .head_id()
+        .ok()
+        .map(gix::Id::detach)
+        .into_iter()
+        .collect();
+    let commit = repo.commit("HEAD", message, tree, parents)?;
+    write_index(lock, &index, &changed)?;
+    Ok(commit.to_string())
+}
+
+/// Stages `paths` like `git add`, which also marks them resolved during a merge.
+pub(super) fn stage_files(paths: &[PathBuf]) -> Result<()> {
+    let first = paths.first().ok_or("Nothing to stage")?;
+    let repo = gix::discover(first.parent().ok_or("Invalid file path")?)?;
+    let lock = lock_index(&repo)?;
+    let mut index: gix::index::File = (**repo.index_or_empty()?).clone();
+    let changed = update_index(&repo, &mut index, paths)?;
+    write_index(lock, &index, &changed)
+}
+
+/// Updates the entries for `paths`, returning them relative to the repository root.
+fn update_index(
+    repo: &gix::Repository,
+    index: &mut gix::index::File,
+    paths: &[PathBuf],
+) -> Result<Vec<BString>> {
+    if index.link().is_some() {
+        return Err(From::from(
+            "Split indexes are not supported, turn them off with git update-index --no-split-index",
+        ));
+    }
+    let mut changed = Vec::new();
+    for path in paths {
+        let relative_path = gix::path::into_bstr(repo_relative_path(repo, path)?).into_owned();
+        changed.push(relative_path.clone());
+        if !path.exists() {
+            index.remove_entries(|_, entry_path, _| entry_path == relative_path);
+            continue;
+        }
+        // Staging a file resolves any conflict recorded for it.
+        index.remove_entries(|_, entry_path, entry| {
+            entry_path == relative_path && entry.stage() != Stage::Unconflicted
+        });
+        let id = repo.write_blob(fs::read(path)?)?.detach();
+        let stat = Stat::from_fs(&gix::index::fs::Metadata::from_path_no_follow(path)?)?;
+        if let Ok(position) = index.entry_index_by_path(relative_path.as_bstr()) {
+            let entry = &mut index.entries_mut()[position];
+            entry.id = id;
+            entry.stat = stat;
+        } else {
+            index.dangerously_push_entry(
+                stat,
+                id,
+                Flags::empty(),
+                Mode::FILE,
+                relative_path.as_bstr(),
+            );
+            index.sort_entries();
+        }
+    }
+    Ok(changed)
+}
+
+/// Locks the index like git does while changing it, failing if git already holds the lock.
+fn lock_index(repo: &gix::Repository) -> Result<gix::lock::File> {
+    Ok(gix::lock::File::acquire_to_update_resource(
+        repo.index_path(),
+        gix::lock::acquire::Fail::Immediately,
+        None,
+    )?)
+}
+
+/// Writes `index` back through the `lock` taken before reading it. The cached trees holding any of the `changed`
+/// paths are invalidated, like git does, and the record of resolved conflicts is kept. The
+/// untracked cache and the fsmonitor state are left out for git to rebuild, as they would no
+/// longer match the entries.
+fn write_index(
+    mut lock: gix::lock::File,
+    index: &gix::index::File,
+    changed: &[BString],
+) -> Result<()> {
+    let mut out = Vec::new();
+    // Written without the trailing checksum, which is added once the extensions are in.
+    gix::index::State::write_to(
+        index,
+        &mut out,
+        gix::index::write::Options {
+            extensions: gix::index::write::Extensions::None,
+            ..Default::default()
+        },
+    )?;
+    if let Some(tree) = index.tree() {
+        let mut tree = tree.clone();
+        for path in changed {
+            invalidate_tree(&mut tree, path.as_bstr());
+        }
+        tree.write_to(&mut out)?;
+    }
+    let object_hash = index.object_hash();
+    let on_disk = fs::read(index.path()).unwrap_or_default();
+    if let Some(offset) = extensions_offset(&on_disk, object_hash) {
+        let extensions =
+            gix::index::extension::Iter::new_without_checksum(&on_disk[offset..], object_hash);
+        for (signature, data) in extensions.into_iter().flatten() {
+            if signature == *b"REUC" {
+                out.extend_from_slice(&signature);
+                out.extend_from_slice(&u32::try_from(data.len())?.to_be_bytes());
+                out.extend_from_slice(data);
+            }
+        }
+    }
+    let mut hasher = gix::features::hash::hasher(object_hash);
+    hasher.update(&out);
+    out.extend_from_slice(&hasher.digest());
+    lock.write_all(&out)?;
+    lock.commit()?;
+    Ok(())
+}
+
+/// Marks the cached trees on the way to `path` as needing to be recomputed.
+fn invalidate_tree(tree: &mut gix::index::extension::Tree, path: &BStr) {
+    tree.num_entries = None;
+    if let Some((dir, rest)) = path.split_once_str("/") {
+        if let Some(child) = tree
+            .children
+            .iter_mut()
+            .find(|child| child.name.as_slice() == dir)
+        {
+            invalidate_tree(child, rest.as_bstr());
+        }
+    }
+}
+
+/// Where the extensions start in the index file `data`, past its header and entries.
+fn extensions_offset(data: &[u8], object_hash: gix::hash::Kind) -> Option<usize> {
+    let read_u32 = |offset: usize| {
+        Some(u32::from_be_bytes(
+            data.get(offset..offset + 4)?.try_into().ok()?,
+        ))
+    };
+    let version = read_u32(4)?;
+    let count = read_u32(8)?;
+    // Stat data, the object ID, then the flags.
+    let fixed = 40 + object_hash.len_in_bytes();
+    let mut offset = 12;
+    for _ in 0..count {
+        let start = offset;
+        let flags = u16::from_be_bytes(
+            data.get(offset + fixed..offset + fixed + 2)?
+                .try_into()
+                .ok()?,
+        );
+        offset += fixed + 2;
+        if flags & 0x4000 != 0 {
+            // Extended flags.
+            offset += 2;
+        }
+        if version == 4 {
+            // The path is stored as a varint of how much of the previous one to drop, then the rest.
+            while data.get(offset)? & 0x80 != 0 {
+                offset += 1;
+            }
+            offset += 1;
+            offset += data.get(offset..)?.iter().position(|&byte| byte == 0)? + 1;
+        } else {
+            offset += data.get(offset..)?.iter().position(|&byte| byte == 0)? + 1;
+            // Padded with NULs to a multiple of eight bytes.
+            offset = start + (offset - start).div_ceil(8) * 8;
+        }
+    }
+    Some(offset)
+}
+
+/// Whether a merge is waiting to be committed, in which case committing on our own would
+/// lose its second parent.
+pub(super) fn merge_in_progress<P: AsRef<Path>>(path: P) -> Result<bool> {
+    Ok(gix::discover(path)?.git_dir().join("MERGE_HEAD").exists())
+}
+
+/// Files in `dir` that both sides of an unfinished merge added with different contents, as
+/// `(path, ours, theirs)`. Git records these as conflicts without a common ancestor.
+pub(super) fn conflicting_additions(dir: &Path) -> Result<Vec<(PathBuf, String, String)>> {
+    let repo = gix::discover(dir)?;
+    let work_dir = repo
+        .work_dir()
+        .ok_or("Bare repositories are not supported")?;
+    let mut prefix = gix::path::into_bstr(
+        dir.canonicalize()?
+            .strip_prefix(work_dir.canonicalize()?)?
+            .to_path_buf(),
+    )
+    .into_owned();
+    prefix.push(b'/');
+    let index = repo.index_or_empty()?;
+    let mut stages: BTreeMap<BString, Vec<(Stage, ObjectId)>> = BTreeMap::new();
+    for entry in index.entries() {
+        let path = entry.path(&index);
+        if entry.stage() != Stage::Unconflicted && path.starts_with(prefix.as_slice()) {
+            stages
+                .entry(path.to_owned())
+                .or_default()
+                .push((entry.stage(), entry.id));
+        }
+    }
+    let mut additions = Vec::new();
+    for (path, entries) in stages {
+        let blob = |stage| -> Result<Option<String>> {
+            match entries.iter().find(|(s, _)| *s == stage) {
+                Some((_, id)) => Ok(Some(String::from_utf8(
+                    repo.find_object(*id)?.detach().data,
+                )?)),
+                None => Ok(None),
+            }
+        };
+        if blob(Stage::Base)?.is_some() {
+            continue;
+        }
+        if let (Some(ours), Some(theirs)) = (blob(Stage::Ours)?, blob(Stage::Theirs)?) {
+            additions.push((
+                work_dir.join(gix::path::from_bstr(path.as_bstr())),
+                ours,
+                theirs,
+            ));
+        }
+    }
+    Ok(additions)
+}
+
+/// Every file committed in HEAD, keyed by its path. Empty for a repository without commits.
+fn head_files(repo: &gix::Repository) -> Result<BTreeMap<BString, (ObjectId, Option<EntryMode>)>> {
+    let Ok(tree) = repo.head_tree_id() else {
+        return Ok(BTreeMap::new());
+    };
+    Ok(tree_files(&tree.object()?.into_tree())?
+        .into_iter()
+        .map(|(path, (oid, mode))| (path, (oid, Some(mode))))
+        .collect())
+}
+
+fn tree_files(tree: &gix::Tree<'_>) -> Result<BTreeMap<BString, (ObjectId, EntryMode)>> {
+    let mut recorder = gix::traverse::tree::Recorder::default();
+    tree.traverse().breadthfirst(&mut recorder)?;
+    Ok(recorder
+        .records
+        .into_iter()
+        .filter(|entry| !entry.mode.is_tree())
+        .map(|entry| (entry.filepath, (entry.oid, entry.mode)))
+        .collect())
+}
+
+/// Writes the nested trees for a flat list of files and returns the root tree ID.
+fn write_tree(
+    repo: &gix::Repository,
+    files: BTreeMap<BString, (ObjectId, EntryMode)>,
+) -> Result<ObjectId> {
+    let mut tree = gix::objs::Tree::empty();
+    let mut directories: BTreeMap<BString, BTreeMap<BString, (ObjectId, EntryMode)>> =
+        BTreeMap::new();
+    for (path, (oid, mode)) in files {
+        match path.split_once_str("/") {
+            Some((directory, rest)) => {
+                directories
+                    .entry(directory.into())
+                    .or_default()
+                    .insert(rest.into(), (oid, mode));
+            }
+            None => tree.entries.push(gix::objs::tree::Entry {
+                mode,
+                filename: path,
+                oid,
+            }),
+        }
+    }
+    for (directory, files) in directories {
+        tree.entries.push(gix::objs::tree::Entry {
+            mode: gix::objs::tree::EntryKind::Tree.into(),
+            filename: directory,
+            oid: write_tree(repo, files)?,
+        });
+    }
+    tree.entries.sort();
+    Ok(repo.write_object(&tree)?.detach())
+}
+
+fn blob_at(commit: &gix::Commit<'_>, path: &Path, buf: &mut Vec<u8>) -> Result<Option<ObjectId>> {
+    let entry = commit.tree()?.lookup_entry_by_path(path, buf)?;
+    Ok(entry.map(|entry| entry.object_id()))
+}
+
+fn repo_relative_path(repo: &gix::Repository, path: &Path) -> Result<PathBuf> {
+    let dir = path.parent().ok_or("Invalid file path")?;
+    let file_name = path.file_name().ok_or("Invalid file path")?;
+    Ok(repo_relative_dir(repo, dir)?.join(file_name))
+}
+
+fn repo_relative_dir(repo: &gix::Repository, dir: &Path) -> Result<PathBuf> {
+    let root = repo
+        .work_dir()
+        .ok_or("Bare repositories are not supported")?
+        .canonicalize()?;
+    Ok(dir.canonicalize()?.strip_prefix(root)?.to_path_buf())
+}
+
 #[cfg(test)]
 mod tests {
+    use super::super::test_utils::TempDir;
+    use super::super::{record_write, WRITTEN_FILES};
     use super::*;
+    use std::process::Command;
 
     #[test]
     fn test_find_git_root() {
         let repo = find_git_root(".").unwrap();
         assert_eq!(repo, Path::new("../").canonicalize().unwrap());
     }
+
+    fn write_file(repo: &TempDir, name: &str, contents: &str) -> PathBuf {
+        let path = repo.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn git(repo: &TempDir, args: &[&str], input: Option<&str>) -> String {
+        let mut child = Command::new("git")
+            .args(args)
+            .current_dir(repo.path())
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .unwrap();
+        if let Some(input) = input {
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(input.as_bytes())
+                .unwrap();
+        }
+        let output = child.wait_with_output().unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_commit_on_unborn_head() {
+        let repo = TempDir::new_repo("unborn");
+        let path = write_file(&repo, "T0001.toml", "id = \"T0001\"\n");
+        assert_eq!(head_commit(repo.path()).unwrap(), None);
+
+        let commit = commit_files(std::slice::from_ref(&path), "first").unwrap();
+
+        assert_eq!(head_commit(repo.path()).unwrap(), Some(commit.clone()));
+        assert_eq!(first_parent(repo.path(), &commit).unwrap(), None);
+        assert_eq!(
+            file_at_revision(&path, "HEAD").unwrap().as_deref(),
+            Some("id = \"T0001\"\n")
+        );
+        assert!(staged_paths(repo.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_no_commit_while_index_is_locked() {
+        let repo = TempDir::new_repo("locked");
+        let path = write_file(&repo, "T0001.toml", "id = \"T0001\"\n");
+        let lock = repo.path().join(".git/index.lock");
+        fs::write(&lock, "").unwrap();
+
+        assert!(commit_files(std::slice::from_ref(&path), "first").is_err());
+        assert_eq!(head_commit(repo.path()).unwrap(), None);
+        // The lock still belongs to whoever took it.
+        assert!(lock.exists());
+    }
+
+    #[test]
+    fn test_check_new_branch() {
+        let repo = TempDir::new_repo("branches");
+        let path = write_file(&repo, "T0001.toml", "id = \"T0001\"\n");
+        commit_files(std::slice::from_ref(&path), "first").unwrap();
+        let main = current_branch(repo.path()).unwrap().unwrap();
+        check_new_branch(repo.path(), "T0001-first").unwrap();
+        create_and_checkout_branch(repo.path(), "T0001-first").unwrap();
+        write_file(&repo, "T0001.toml", "id = \"T0001\"\ntitle = \"Changed\"\n");
+        commit_files(std::slice::from_ref(&path), "second").unwrap();
+
+        check_new_branch(repo.path(), "T0001-first").unwrap();
+        assert!(check_new_branch(repo.path(), &main).is_err());
+        assert!(check_new_branch(repo.path(), "no..dots").is_err());
+        assert_eq!(
+            current_branch(repo.path()).unwrap().as_deref(),
+            Some("T0001-first")
+        );
+    }
+
+    #[test]
+    fn test_stage_and_commit_deletion() {
+        let repo = TempDir::new_repo("deletion");
+        let kept = write_file(&repo, "T0001.toml", "id = \"T0001\"\n");
+        let deleted = write_file(&repo, "T0002.toml", "id = \"T0002\"\n");
+        let first = commit_files(&[kept.clone(), deleted.clone()], "first").unwrap();
+        fs::remove_file(&deleted).unwrap();
+
+        stage_files(std::slice::from_ref(&deleted)).unwrap();
+        assert_eq!(
+            staged_paths(repo.path()).unwrap(),
+            BTreeSet::from([BString::from("T0002.toml")])
+        );
+        let second = commit_files(std::slice::from_ref(&deleted), "delete").unwrap();
+
+        assert_eq!(first_parent(repo.path(), &second).unwrap(), Some(first));
+        assert_eq!(file_at_revision(&deleted, "HEAD").unwrap(), None);
+        assert!(file_at_revision(&kept, "HEAD").unwrap().is_some());
+        assert!(staged_paths(repo.path()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_dir_history() {
+        let repo = TempDir::new_repo("dir-history");
+        let edited = write_file(&repo, "tickets/T0001.toml", "v1\n");
+        let deleted = write_file(&repo, "tickets/T0002.toml", "v1\n");
+        let outside = write_file(&repo, "notes.toml", "v1\n");
+        commit_files(&[edited.clone(), deleted.clone(), outside.clone()], "add").unwrap();
+        write_file(&repo, "tickets/T0001.toml", "v2\n");
+        write_file(&repo, "notes.toml", "v2\n");
+        commit_files(&[edited, outside], "edit").unwrap();
+        fs::remove_file(&deleted).unwrap();
+        commit_files(&[deleted], "delete").unwrap();
+
+        let history = dir_history(repo.path().join("tickets"), |_| true).unwrap();
+
+        let summaries = |name: &str| -> Vec<(String, Option<String>)> {
+            history[name]
+                .iter()
+                .map(|revision| (revision.summary.clone(), revision.contents.clone()))
+                .collect()
+        };
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            summaries("T0001.toml"),
+            [
+                ("edit".to_owned(), Some("v2\n".to_owned())),
+                ("add".to_owned(), Some("v1\n".to_owned()))
+            ]
+        );
+        assert_eq!(
+            summaries("T0002.toml"),
+            [
+                ("delete".to_owned(), None),
+                ("add".to_owned(), Some("v1\n".to_owned()))
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lines_changed() {
+        let repo = TempDir::new_repo("lines-changed");
+        let edited = write_file(&repo, "src/main.rs", "one\ntwo\n");
+        let deleted = write_file(&repo, "docs/old.md", "old\n");
+        let untouched = write_file(&repo, "README.md", "readme\n");
+        let first = commit_files(&[edited.clone(), deleted.clone(), untouched], "first").unwrap();
+        write_file(&repo, "src/main.rs", "one\nthree\nfour\n");
+        let added = write_file(&repo, "docs/new.md", "new\n");
+        fs::remove_file(&deleted).unwrap();
+        let second = commit_files(&[edited, deleted, added], "second").unwrap();
+
+        let lines = lines_changed(repo.path(), &[&second, &first]).unwrap();
+
+        assert_eq!(lines, [(3, 2), (4, 0)]);
+    }
+
+    #[test]
+    fn test_refuses_to_record_while_other_files_are_staged() {
+        let repo = TempDir::new_repo("staged");
+        let unrelated = write_file(&repo, "notes.txt", "draft\n");
+        stage_files(&[unrelated]).unwrap();
+        let ticket = write_file(&repo, "T0001.toml", "id = \"T0001\"\n");
+
+        let error = record_write(&ticket, "T0001".to_owned()).unwrap_err();
+
+        assert!(error.to_string().contains("notes.txt"));
+        assert!(WRITTEN_FILES.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_index_keeps_extensions() {
+        let repo = TempDir::new_repo("extensions");
+        let ticket = write_file(&repo, "tickets/T0001.toml", "id = \"T0001\"\n");
+        let doc = write_file(&repo, "docs/README.md", "Docs\n");
+        commit_files(&[ticket.clone(), doc], "first").unwrap();
+        // Fills in the cached trees, then resolves a conflict so git records how to undo it.
+        git(&repo, &["read-tree", "HEAD"], None);
+        let blob = git(&repo, &["hash-object", "-w", "--stdin"], Some("side\n"));
+        let blob = blob.trim();
+        let conflict = format!(
+            "100644 {blob} 1\tconflict.txt\n100644 {blob} 2\tconflict.txt\n\
+             100644 {blob} 3\tconflict.txt\n"
+        );
+        git(&repo, &["update-index", "--index-info"], Some(&conflict));
+        write_file(&repo, "conflict.txt", "resolved\n");
+        git(&repo, &["add", "conflict.txt"], None);
+
+        write_file(
+            &repo,
+            "tickets/T0001.toml",
+            "id = \"T0001\"\ntitle = \"Changed\"\n",
+        );
+        stage_files(&[ticket]).unwrap();
+
+        let repo_handle = gix::discover(repo.path()).unwrap();
+        let index = repo_handle.index().unwrap();
+        let tree = index.tree().unwrap();
+        let child = |name: &str| {
+            tree.children
+                .iter()
+                .find(|child| child.name.as_slice() == name.as_bytes())
+                .unwrap()
+        };
+        assert_eq!(tree.num_entries, None);
+        assert_eq!(child("tickets").num_entries, None);
+        assert_eq!(child("docs").num_entries, Some(1));
+        assert!(index.resolve_undo().is_some());
+        // git reads the index back, and agrees on what is staged.
+        let diff = git(&repo, &["diff", "--cached", "--name-only"], None);
+        assert_eq!(diff, "conflict.txt\ntickets/T0001.toml\n");
+    }
+
+    #[test]
+    fn test_extensions_offset_in_every_index_version() {
+        // Version 2 can't hold extended flags, git moves to version 3 for them.
+        for (version, intent_to_add) in [("2", false), ("3", true), ("4", true)] {
+            let repo = TempDir::new_repo(&format!("index-v{version}"));
+            let ticket = write_file(&repo, "tickets/T0001.toml", "id = \"T0001\"\n");
+            write_file(&repo, "tickets/T0002.toml", "id = \"T0002\"\n");
+            write_file(&repo, "a-name-that-needs-more-padding.md", "Docs\n");
+            git(&repo, &["add", "."], None);
+            if intent_to_add {
+                write_file(&repo, "tickets/T0003.toml", "id = \"T0003\"\n");
+                git(
+                    &repo,
+                    &["add", "--intent-to-add", "tickets/T0003.toml"],
+                    None,
+                );
+            }
+            git(&repo, &["update-index", "--index-version", version], None);
+            // Records the cached trees, the first extension.
+            git(&repo, &["write-tree"], None);
+
+            let data = fs::read(repo.path().join(".git/index")).unwrap();
+            assert_eq!(data[4..8], version.parse::<u32>().unwrap().to_be_bytes());
+            let offset = extensions_offset(&data, gix::hash::Kind::Sha1).unwrap();
+            assert_eq!(
+                &data[offset..offset + 4],
+                b"TREE",
+                "index version {version}"
+            );
+
+            write_file(
+                &repo,
+                "tickets/T0001.toml",
+                "id = \"T0001\"\ntitle = \"Changed\"\n",
+            );
+            stage_files(&[ticket]).unwrap();
+            // git reads the rewritten index back.
+            let staged = git(&repo, &["show", ":tickets/T0001.toml"], None);
+            assert_eq!(staged, "id = \"T0001\"\ntitle = \"Changed\"\n");
+        }
+    }
 }
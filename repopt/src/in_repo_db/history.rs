@@ -0,0 +1,308 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use super::get_or_create_irdb_dirs;
+use super::git_utils::{self, FileRevision};
+use super::structs::{Ticket, TicketId, User};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// A committed version of a ticket file. `ticket` is `None` when the commit deleted the file
+/// or the revision can no longer be read as a `Ticket`.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct TicketRevision {
+    pub commit: String,
+    pub author: User,
+    pub time: DateTime<Utc>,
+    pub summary: String,
+    #[serde(skip)]
+    pub ticket: Option<Ticket>,
+}
+
+impl From<FileRevision> for TicketRevision {
+    fn from(revision: FileRevision) -> Self {
+        let ticket = revision
+            .contents
+            .as_deref()
+            .and_then(|contents| toml::from_str(contents).ok());
+        TicketRevision {
+            commit: revision.commit,
+            author: revision.author,
+            time: revision.time,
+            summary: revision.summary,
+            ticket,
+        }
+    }
+}
+
+/// Every committed revision of the ticket's file, newest first.
+pub(crate) fn ticket_history(id: &TicketId) -> Result<Vec<TicketRevision>> {
+    let irdb_paths = get_or_create_irdb_dirs()?;
+    let ticket_file = irdb_paths.tickets.join(format!("{id}.toml"));
+    let revisions = git_utils::file_history(ticket_file)?;
+    Ok(revisions.into_iter().map(TicketRevision::from).collect())
+}
+
+/// [`ticket_history`] of every ticket, read in a single walk of the history.
+pub(crate) fn ticket_histories() -> Result<BTreeMap<TicketId, Vec<TicketRevision>>> {
+    let irdb_paths = get_or_create_irdb_dirs()?;
+    let is_toml =
+        |name: &str| Path::new(name).extension().and_then(|ext| ext.to_str()) == Some("toml");
+    let histories = git_utils::dir_history(&irdb_paths.tickets, is_toml)?;
+    Ok(histories
+        .into_iter()
+        .filter_map(|(name, revisions)| {
+            let id = Path::new(&name).file_stem()?.to_str()?;
+            let revisions = revisions.into_iter().map(TicketRevision::from).collect();
+            Some((TicketId::from(id), revisions))
+        })
+        .collect())
+}
+
+/// One field that differs between two versions of a ticket. `from` is absent when the field
+/// was added and `to` when it was removed.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct FieldChange {
+    pub field: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Value>,
+}
+
+/// A commit that changed the ticket, with the fields it changed.
+#[derive(Serialize, Debug, Clone)]
+pub(crate) struct ChangeLogEntry {
+    pub commit: String,
+    pub author: User,
+    pub time: DateTime<Utc>,
+    pub summary: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// The field-level change log of a ticket, newest first.
+pub(crate) fn change_log(id: &TicketId) -> Result<Vec<ChangeLogEntry>> {
+    Ok(build_change_log(&ticket_history(id)?))
+}
+
+/// The ticket as committed in `revision`, or `None` if it did not exist there.
+pub(crate) fn ticket_at_revision(id: &TicketId, revision: &str) -> Result<Option<Ticket>> {
+    let irdb_paths = get_or_create_irdb_dirs()?;
+    let ticket_file = irdb_paths.tickets.join(format!("{id}.toml"));
+    match git_utils::file_at_revision(ticket_file, revision)? {
+        Some(contents) => Ok(Some(toml::from_str(&contents)?)),
+        None => Ok(None),
+    }
+}
+
+/// Compares two versions of a ticket field by field, in field name order. A missing ticket
+/// counts as having no fields, so creations and deletions list every field.
+pub(crate) fn diff_tickets(old: Option<&Ticket>, new: Option<&Ticket>) -> Vec<FieldChange> {
+    let (old, new) = (ticket_fields(old), ticket_fields(new));
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| old.get(*name) != new.get(*name))
+        .map(|name| FieldChange {
+            field: name.clone(),
+            from: old.get(name).cloned(),
+            to: new.get(name).cloned(),
+        })
+        .collect()
+}
+
+/// Top level fields of the ticket as it would be written, `extra` keys included.
+fn ticket_fields(ticket: Option<&Ticket>) -> serde_json::Map<String, Value> {
+    match ticket.map(serde_json::to_value) {
+        Some(Ok(Value::Object(fields))) => fields,
+        _ => serde_json::Map::new(),
+    }
+}
+
+/// Who last set one field of a ticket. The commit details are absent while the value is only
+/// in the working tree.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub(crate) struct FieldBlame {
+    pub field: String,
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+}
+
+/// Field-level blame of the ticket as it is in the working tree.
+pub(crate) fn blame(ticket: &Ticket) -> Result<Vec<FieldBlame>> {
+    Ok(blame_fields(&ticket_history(ticket.id())?, ticket))
+}
+
+fn blame_fields(history: &[TicketRevision], current: &Ticket) -> Vec<FieldBlame> {
+    let revisions: Vec<_> = history
+        .iter()
+        .map(|revision| (revision, ticket_fields(revision.ticket.as_ref())))
+        .collect();
+    ticket_fields(Some(current))
+        .into_iter()
+        .map(|(field, value)| {
+            // Newest first: the value was set by the oldest revision of the unbroken run of
+            // revisions that already had it.
+            let origin = revisions
+                .iter()
+                .take_while(|(_, fields)| fields.get(&field) == Some(&value))
+                .last()
+                .map(|(revision, _)| *revision);
+            FieldBlame {
+                field,
+                value,
+                commit: origin.map(|revision| revision.commit.clone()),
+                author: origin.map(|revision| revision.author.clone()),
+                time: origin.map(|revision| revision.time),
+                summary: origin.map(|revision| revision.summary.clone()),
+            }
+        })
+        .collect()
+}
+
+fn build_change_log(history: &[TicketRevision]) -> Vec<ChangeLogEntry> {
+    history
+        .iter()
+        .enumerate()
+        .map(|(index, revision)| {
+            let previous = history
+                .get(index + 1)
+                .and_then(|older| older.ticket.as_ref());
+            ChangeLogEntry {
+                commit: revision.commit.clone(),
+                author: revision.author.clone(),
+                time: revision.time,
+                summary: revision.summary.clone(),
+                changes: diff_tickets(previous, revision.ticket.as_ref()),
+            }
+        })
+        // Commits that only reformatted the file are noise here.
+        .filter(|entry| !entry.changes.is_empty())
+        .collect()
+}
+
+/// When the ticket was last closed, judged from its git `history`. A ticket closed in the
+/// working tree but not yet committed counts as closed now.
+pub(crate) fn closed_at(ticket: &Ticket, history: &[TicketRevision]) -> Option<DateTime<Utc>> {
+    last_closed_at(history, ticket, Utc::now())
+}
+
+fn last_closed_at(
+    history: &[TicketRevision],
+    current: &Ticket,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if current.is_open() {
+        return None;
+    }
+    let committed_open =
+        |revision: &TicketRevision| revision.ticket.as_ref().is_some_and(Ticket::is_open);
+    match history.first() {
+        None => return Some(now),
+        Some(latest) if committed_open(latest) => return Some(now),
+        Some(_) => {}
+    }
+    // Newest first: the close happened in the oldest revision of the run of closed revisions.
+    let closing = history
+        .iter()
+        .take_while(|revision| !committed_open(revision))
+        .last()?;
+    Some(closing.time)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_repo_db::structs::tests::ticket_fixture;
+    use crate::in_repo_db::structs::TicketStatus;
+    use chrono::TimeZone;
+
+    fn ticket(status: TicketStatus) -> Ticket {
+        ticket_fixture("T0001", "Test Ticket", status)
+    }
+
+    fn revision(day: u32, status: TicketStatus) -> TicketRevision {
+        TicketRevision {
+            commit: format!("{day:040}"),
+            author: "Jane Doe".parse().unwrap(),
+            time: Utc.with_ymd_and_hms(2024, 1, day, 12, 0, 0).unwrap(),
+            summary: String::new(),
+            ticket: Some(ticket(status)),
+        }
+    }
+
+    #[test]
+    fn test_last_closed_at() {
+        let now = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let closed = ticket(TicketStatus::Closed);
+        let history = [
+            revision(5, TicketStatus::Closed),
+            revision(4, TicketStatus::Closed),
+            revision(3, TicketStatus::InProgress),
+            revision(2, TicketStatus::Closed),
+            revision(1, TicketStatus::Backlog),
+        ];
+        assert_eq!(
+            last_closed_at(&history, &closed, now),
+            Some(history[1].time)
+        );
+        assert_eq!(last_closed_at(&history[2..], &closed, now), Some(now));
+        assert_eq!(last_closed_at(&[], &closed, now), Some(now));
+        let open = ticket(TicketStatus::Backlog);
+        assert_eq!(last_closed_at(&history, &open, now), None);
+    }
+
+    #[test]
+    fn test_change_log() {
+        let history = [
+            revision(3, TicketStatus::Closed),
+            revision(2, TicketStatus::InProgress),
+            revision(1, TicketStatus::InProgress),
+        ];
+        let log = build_change_log(&history);
+        assert_eq!(log.len(), 2);
+        assert_eq!(
+            log[0].changes,
+            [FieldChange {
+                field: "status".to_owned(),
+                from: Some("InProgress".into()),
+                to: Some("Closed".into()),
+            }]
+        );
+        assert_eq!(log[1].commit, history[2].commit);
+        assert!(log[1].changes.iter().all(|change| change.from.is_none()));
+        let removed = diff_tickets(history[0].ticket.as_ref(), None);
+        assert!(removed.iter().any(|change| change.field == "title"));
+    }
+
+    #[test]
+    fn test_blame_fields() {
+        let history = [
+            revision(3, TicketStatus::Closed),
+            revision(2, TicketStatus::InProgress),
+            revision(1, TicketStatus::InProgress),
+        ];
+        let commit_of = |blame: &[FieldBlame], field: &str| {
+            blame
+                .iter()
+                .find(|entry| entry.field == field)
+                .and_then(|entry| entry.commit.clone())
+        };
+        let blame = blame_fields(&history, &ticket(TicketStatus::Closed));
+        assert_eq!(commit_of(&blame, "status"), Some(history[0].commit.clone()));
+        assert_eq!(commit_of(&blame, "title"), Some(history[2].commit.clone()));
+        let uncommitted = blame_fields(&history, &ticket(TicketStatus::Backlog));
+        assert_eq!(commit_of(&uncommitted, "status"), None);
+    }
+}
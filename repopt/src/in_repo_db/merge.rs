@@ -0,0 +1,169 @@
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use super::structs::Ticket;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// List fields where both sides may add or remove entries independently, so they are merged
+/// entry by entry instead of conflicting as soon as both sides touched them.
+const SET_FIELDS: [&str; 3] = ["labels", "links", "work_log"];
+
+/// The result of a three-way merge of one ticket file.
+pub(crate) struct TicketMerge {
+    /// The merged file. On conflicts it carries git style markers for the conflicting fields.
+    pub contents: String,
+    pub conflicts: Vec<String>,
+}
+
+/// Merges `ours` and `theirs` field by field against their common `base`, which is empty when
+/// both sides added the file. A field only conflicts when both sides changed it differently.
+pub(crate) fn merge_tickets(base: &str, ours: &str, theirs: &str) -> Result<TicketMerge> {
+    let base = if base.trim().is_empty() {
+        Map::new()
+    } else {
+        ticket_fields(base)?
+    };
+    let ours = ticket_fields(ours)?;
+    let theirs = ticket_fields(theirs)?;
+    let keys: BTreeSet<&String> = base
+        .keys()
+        .chain(ours.keys())
+        .chain(theirs.keys())
+        .collect();
+    let mut merged = Map::new();
+    let mut conflicts = Vec::new();
+    for key in keys {
+        let (b, o, t) = (base.get(key), ours.get(key), theirs.get(key));
+        let value = if o == t || b == t {
+            o.cloned()
+        } else if b == o {
+            t.cloned()
+        } else if let (true, Some(Value::Array(o)), Some(Value::Array(t))) =
+            (SET_FIELDS.contains(&key.as_str()), o, t)
+        {
+            Some(Value::Array(merge_entries(b, o, t)))
+        } else {
+            conflicts.push(key.clone());
+            continue;
+        };
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+    if conflicts.is_empty() {
+        let ticket: Ticket = serde_json::from_value(Value::Object(merged))?;
+        return Ok(TicketMerge {
+            contents: toml::to_string(&ticket)?,
+            conflicts,
+        });
+    }
+    // The markers go first: once a table has started, a plain key would end up inside it.
+    let mut contents = String::new();
+    for key in &conflicts {
+        let side = |fields: &Map<String, Value>| -> Result<String> {
+            match fields.get(key) {
+                Some(value) => Ok(format!("{key} = {}\n", toml::Value::try_from(value)?)),
+                None => Ok(String::new()),
+            }
+        };
+        let _ = write!(
+            contents,
+            "<<<<<<< ours\n{}=======\n{}>>>>>>> theirs\n",
+            side(&ours)?,
+            side(&theirs)?
+        );
+    }
+    contents.push_str(&toml::to_string(&merged)?);
+    Ok(TicketMerge {
+        contents,
+        conflicts,
+    })
+}
+
+fn ticket_fields(contents: &str) -> Result<Map<String, Value>> {
+    let ticket: Ticket = toml::from_str(contents)?;
+    match serde_json::to_value(ticket)? {
+        Value::Object(fields) => Ok(fields),
+        _ => Err(From::from("A ticket should serialize to a table")),
+    }
+}
+
+/// Our entries minus the ones they removed, followed by the ones only they added.
+fn merge_entries(base: Option<&Value>, ours: &[Value], theirs: &[Value]) -> Vec<Value> {
+    let base = match base {
+        Some(Value::Array(base)) => base.as_slice(),
+        _ => &[],
+    };
+    let mut merged: Vec<Value> = ours
+        .iter()
+        .filter(|entry| !base.contains(entry) || theirs.contains(entry))
+        .cloned()
+        .collect();
+    for entry in theirs {
+        if !base.contains(entry) && !merged.contains(entry) {
+            merged.push(entry.clone());
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_repo_db::structs::TicketStatus;
+
+    const BASE: &str = r#"
+id = "T0001"
+title = "Fix login"
+description = "d"
+status = "Backlog"
+type = "Bug"
+labels = ["auth"]
+area = "web"
+
+[project]
+id = "P0001"
+"#;
+
+    fn edit(from: &str, to: &str) -> String {
+        BASE.replace(from, to)
+    }
+
+    #[test]
+    fn test_merge_different_fields() {
+        let ours = edit("status = \"Backlog\"", "status = \"InProgress\"");
+        let theirs = edit("title = \"Fix login\"", "title = \"Fix the login\"")
+            .replace("area = \"web\"", "area = \"api\"");
+        let merge = merge_tickets(BASE, &ours, &theirs).unwrap();
+        assert!(merge.conflicts.is_empty());
+        let ticket: Ticket = toml::from_str(&merge.contents).unwrap();
+        assert_eq!(ticket.title().to_string(), "Fix the login");
+        assert_eq!(*ticket.status(), TicketStatus::InProgress);
+        assert!(merge.contents.contains("area = \"api\""));
+    }
+
+    #[test]
+    fn test_merge_labels_from_both_sides() {
+        let ours = edit("[\"auth\"]", "[\"auth\", \"perf\"]");
+        let theirs = edit("[\"auth\"]", "[\"ui\"]");
+        let merge = merge_tickets(BASE, &ours, &theirs).unwrap();
+        assert!(merge.conflicts.is_empty());
+        assert!(merge.contents.contains("labels = [\"perf\", \"ui\"]"));
+    }
+
+    #[test]
+    fn test_merge_conflicting_field() {
+        let ours = edit("Fix login", "Fix login on Safari");
+        let theirs = edit("Fix login", "Fix login on Firefox");
+        let merge = merge_tickets(BASE, &ours, &theirs).unwrap();
+        assert_eq!(merge.conflicts, ["title"]);
+        assert!(merge.contents.starts_with(
+            "<<<<<<< ours\ntitle = \"Fix login on Safari\"\n=======\n\
+             title = \"Fix login on Firefox\"\n>>>>>>> theirs\n"
+        ));
+        assert!(!merge.contents.contains("title = \"Fix login\""));
+    }
+}
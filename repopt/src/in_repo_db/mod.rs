@@ -1,20 +1,41 @@
+pub mod commits;
+pub mod doctor;
 mod git_utils;
+pub mod history;
+pub mod merge;
+pub mod renumber;
+pub mod settings;
 pub mod structs;
+#[cfg(test)]
+mod test_utils;
 mod toml_utils;
 
 use serde::Serialize;
 use std::collections::BTreeMap;
 use std::env;
 use std::fs::{self, File};
-use std::io::Write;
+use std::io::{ErrorKind, Write};
 use std::path::{Path, PathBuf};
-use std::sync::OnceLock;
-use structs::{InRepoDB, Project, ProjectId, Ticket, TicketId};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+use settings::Settings;
+use structs::{
+    Comment, InRepoDB, Iteration, IterationId, Milestone, MilestoneId, Project, ProjectId,
+    RunningTimer, Ticket, TicketId, User,
+};
 
 use crate::config::CONFIG;
 
+const SETTINGS_FILE: &str = "config.toml";
 const PROJECTS_DIR: &str = "projects";
+const MILESTONES_DIR: &str = "milestones";
+const ITERATIONS_DIR: &str = "iterations";
 const TICKETS_DIR: &str = "tickets";
+const COMMENTS_DIR: &str = "comments";
+const TIMER_FILE: &str = "repopt-timer.toml";
+const MERGE_DRIVER: &str = "repopt";
+const LAST_PROCESSED_FILE: &str = "repopt-last-processed-commit";
 
 type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
 type Result<T> = std::result::Result<T, Error>;
@@ -28,8 +49,12 @@ fn ensure_dir_exists(dir: &Path) -> Result<()> {
 
 #[derive(Clone)]
 struct IRDBPaths {
+    base: PathBuf,
     tickets: PathBuf,
     projects: PathBuf,
+    milestones: PathBuf,
+    iterations: PathBuf,
+    comments: PathBuf,
 }
 
 static IRDB_PATHS: OnceLock<IRDBPaths> = OnceLock::new();
@@ -44,39 +69,223 @@ fn get_or_create_irdb_dirs() -> Result<IRDBPaths> {
     if base_dir.is_relative() {
         base_dir = git_utils::find_git_root(env::current_dir()?)?.join(base_dir);
     }
+    let irdb_paths = create_irdb_dirs(base_dir)?;
+    let _ = IRDB_PATHS.set(irdb_paths.clone());
+    Ok(irdb_paths)
+}
+
+fn create_irdb_dirs(base_dir: PathBuf) -> Result<IRDBPaths> {
     let tickets_dir = base_dir.join(TICKETS_DIR);
     let projects_dir = base_dir.join(PROJECTS_DIR);
-    [&base_dir, &tickets_dir, &projects_dir]
-        .iter()
-        .try_for_each(|dir| ensure_dir_exists(dir))?;
-    let irdb_paths = IRDBPaths {
+    let milestones_dir = base_dir.join(MILESTONES_DIR);
+    let iterations_dir = base_dir.join(ITERATIONS_DIR);
+    let comments_dir = base_dir.join(COMMENTS_DIR);
+    [
+        &base_dir,
+        &tickets_dir,
+        &projects_dir,
+        &milestones_dir,
+        &iterations_dir,
+        &comments_dir,
+    ]
+    .iter()
+    .try_for_each(|dir| ensure_dir_exists(dir))?;
+    Ok(IRDBPaths {
+        base: base_dir,
         tickets: tickets_dir,
         projects: projects_dir,
-    };
-    let _ = IRDB_PATHS.set(irdb_paths.clone());
-    Ok(irdb_paths)
+        milestones: milestones_dir,
+        iterations: iterations_dir,
+        comments: comments_dir,
+    })
 }
 
 pub(super) fn collect_in_repo_db() -> Result<InRepoDB> {
-    let irdb_paths = get_or_create_irdb_dirs()?;
+    load_in_repo_db(&get_or_create_irdb_dirs()?)
+}
+
+fn load_in_repo_db(irdb_paths: &IRDBPaths) -> Result<InRepoDB> {
     let projects = collect_projects(&irdb_paths.projects)?;
+    let milestones = collect_milestones(&irdb_paths.milestones)?;
+    let iterations = collect_iterations(&irdb_paths.iterations)?;
     let tickets = collect_tickets(&irdb_paths.tickets)?;
+    let comments = collect_comments(&irdb_paths.comments)?;
+
+    Ok(InRepoDB::new(
+        projects, milestones, iterations, tickets, comments,
+    ))
+}
 
-    Ok(InRepoDB::new(projects, tickets))
+pub(crate) fn load_settings() -> Result<Settings> {
+    let irdb_paths = get_or_create_irdb_dirs()?;
+    let settings_file = irdb_paths.base.join(SETTINGS_FILE);
+    if !settings_file.exists() {
+        return Ok(Settings::default());
+    }
+    let settings_contents = fs::read_to_string(settings_file)?;
+    Ok(toml::from_str(&settings_contents)?)
+}
+
+/// A cheap fingerprint of everything under the IRDB directory (file count and newest
+/// modification time), used to notice when another process has changed the files.
+pub(crate) fn modification_stamp() -> Result<(usize, Option<SystemTime>)> {
+    // Files can vanish while they are being listed, e.g. during a `git checkout`. Those are
+    // skipped, as the next stamp will differ anyway.
+    fn visit(dir: &Path, stamp: &mut (usize, Option<SystemTime>)) -> Result<()> {
+        let entries = match fs::read_dir(dir) {
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            entries => entries?,
+        };
+        for entry in entries {
+            let entry = entry?;
+            let metadata = match entry.metadata() {
+                Err(e) if e.kind() == ErrorKind::NotFound => continue,
+                metadata => metadata?,
+            };
+            if metadata.is_dir() {
+                visit(&entry.path(), stamp)?;
+            } else {
+                stamp.0 += 1;
+                stamp.1 = stamp.1.max(Some(metadata.modified()?));
+            }
+        }
+        Ok(())
+    }
+    let irdb_paths = get_or_create_irdb_dirs()?;
+    let mut stamp = (0, None);
+    visit(&irdb_paths.base, &mut stamp)?;
+    Ok(stamp)
+}
+
+// The running timer is per clone, so it is kept inside `.git` where it is never committed.
+pub(crate) fn load_timer() -> Result<Option<RunningTimer>> {
+    let timer_file = git_utils::find_git_dir(env::current_dir()?)?.join(TIMER_FILE);
+    if !timer_file.exists() {
+        return Ok(None);
+    }
+    Ok(Some(toml::from_str(&fs::read_to_string(timer_file)?)?))
+}
+
+pub(crate) fn save_timer(timer: Option<&RunningTimer>) -> Result<()> {
+    let timer_file = git_utils::find_git_dir(env::current_dir()?)?.join(TIMER_FILE);
+    match timer {
+        Some(timer) => fs::write(timer_file, toml::to_string(timer)?)?,
+        None if timer_file.exists() => fs::remove_file(timer_file)?,
+        None => {}
+    }
+    Ok(())
+}
+
+// Like the timer, the commits `process-commits` has seen are tracked per clone.
+pub(crate) fn load_last_processed_commit() -> Result<Option<String>> {
+    let file = git_utils::find_git_dir(env::current_dir()?)?.join(LAST_PROCESSED_FILE);
+    if !file.exists() {
+        return Ok(None);
+    }
+    Ok(Some(fs::read_to_string(file)?.trim().to_owned()))
+}
+
+pub(crate) fn save_last_processed_commit(commit: &str) -> Result<()> {
+    let file = git_utils::find_git_dir(env::current_dir()?)?.join(LAST_PROCESSED_FILE);
+    fs::write(file, format!("{commit}\n"))?;
+    Ok(())
+}
+
+pub(crate) fn head_commit() -> Result<Option<String>> {
+    git_utils::head_commit(env::current_dir()?)
+}
+
+pub(crate) fn current_branch() -> Result<Option<String>> {
+    git_utils::current_branch(env::current_dir()?)
+}
+
+pub(crate) fn check_new_branch(branch: &str) -> Result<()> {
+    git_utils::check_new_branch(env::current_dir()?, branch)
+}
+
+pub(crate) fn create_and_checkout_branch(branch: &str) -> Result<()> {
+    git_utils::create_and_checkout_branch(env::current_dir()?, branch)
+}
+
+/// Points git at `repopt merge-driver` for ticket files, through `.gitattributes` in the root
+/// of the repository and the merge driver definition in its config.
+pub(crate) fn register_merge_driver() -> Result<()> {
+    let irdb_paths = get_or_create_irdb_dirs()?;
+    let git_root = git_utils::find_git_root(env::current_dir()?)?;
+    let tickets = irdb_paths
+        .tickets
+        .strip_prefix(&git_root)
+        .map_err(|_| "The IRDB directory is outside of the git repository")?;
+    let pattern = format!(
+        "{}/*.toml merge={MERGE_DRIVER}",
+        tickets.to_string_lossy().replace('\\', "/")
+    );
+    let attributes_file = git_root.join(".gitattributes");
+    let attributes = if attributes_file.exists() {
+        fs::read_to_string(&attributes_file)?
+    } else {
+        String::new()
+    };
+    if !attributes.lines().any(|line| line.trim() == pattern) {
+        let separator = if attributes.is_empty() || attributes.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        fs::write(
+            &attributes_file,
+            format!("{attributes}{separator}{pattern}\n"),
+        )?;
+    }
+    git_utils::register_merge_driver(
+        &git_root,
+        MERGE_DRIVER,
+        "repopt field-level ticket merge",
+        "repopt merge-driver %O %A %B",
+    )
+}
+
+pub(crate) fn hooks_dir() -> Result<PathBuf> {
+    git_utils::hooks_dir(env::current_dir()?)
+}
+
+pub(crate) fn current_user() -> Result<User> {
+    git_utils::get_git_user(env::current_dir()?)
 }
 
 fn collect_tickets(ticket_path: &Path) -> Result<BTreeMap<TicketId, Ticket>> {
     let tickets: BTreeMap<_, _> = toml_utils::get_toml_files_in_dir(ticket_path)?
         .into_iter()
         .map(|ticket_file| -> Result<_> {
-            let ticket_contents = fs::read_to_string(ticket_file)?;
+            let ticket_contents = fs::read_to_string(&ticket_file)?;
             let ticket: Ticket = toml::from_str(&ticket_contents)?;
+            structs::ids::validate_id("ticket", &ticket.id().0)
+                .map_err(|err| format!("{err} ({})", ticket_file.display()))?;
             Ok((ticket.id().clone(), ticket))
         })
         .collect::<Result<_>>()?;
     Ok(tickets)
 }
 
+fn collect_comments(comment_path: &Path) -> Result<BTreeMap<TicketId, Vec<Comment>>> {
+    let mut comments: BTreeMap<TicketId, Vec<Comment>> = BTreeMap::new();
+    for ticket_dir in fs::read_dir(comment_path)? {
+        let ticket_dir = ticket_dir?.path();
+        if !ticket_dir.is_dir() {
+            continue;
+        }
+        for comment_file in toml_utils::get_toml_files_in_dir(&ticket_dir)? {
+            let comment_contents = fs::read_to_string(comment_file)?;
+            let comment: Comment = toml::from_str(&comment_contents)?;
+            comments
+                .entry(comment.ticket().clone())
+                .or_default()
+                .push(comment);
+        }
+    }
+    Ok(comments)
+}
+
 fn collect_projects(project_path: &Path) -> Result<BTreeMap<ProjectId, Project>> {
     let projects: BTreeMap<ProjectId, Project> = toml_utils::get_toml_files_in_dir(project_path)?
         .into_iter()
@@ -89,9 +298,37 @@ fn collect_projects(project_path: &Path) -> Result<BTreeMap<ProjectId, Project>>
     Ok(projects)
 }
 
+fn collect_milestones(milestone_path: &Path) -> Result<BTreeMap<MilestoneId, Milestone>> {
+    let milestones: BTreeMap<MilestoneId, Milestone> =
+        toml_utils::get_toml_files_in_dir(milestone_path)?
+            .into_iter()
+            .map(|milestone_file| -> Result<_> {
+                let milestone_contents = fs::read_to_string(milestone_file)?;
+                let milestone: Milestone = toml::from_str(&milestone_contents)?;
+                Ok((milestone.id().clone(), milestone))
+            })
+            .collect::<Result<_>>()?;
+    Ok(milestones)
+}
+
+fn collect_iterations(iteration_path: &Path) -> Result<BTreeMap<IterationId, Iteration>> {
+    let iterations: BTreeMap<IterationId, Iteration> =
+        toml_utils::get_toml_files_in_dir(iteration_path)?
+            .into_iter()
+            .map(|iteration_file| -> Result<_> {
+                let iteration_contents = fs::read_to_string(iteration_file)?;
+                let iteration: Iteration = toml::from_str(&iteration_contents)?;
+                Ok((iteration.id().clone(), iteration))
+            })
+            .collect::<Result<_>>()?;
+    Ok(iterations)
+}
+
 pub(crate) trait IRDBWritableObject: Serialize {
     fn fmt_stub(&self) -> String;
     fn select_path(&self) -> Result<PathBuf>;
+    /// How the object is named in automatic commit messages.
+    fn describe(&self) -> String;
 }
 
 impl IRDBWritableObject for Ticket {
@@ -102,6 +339,9 @@ impl IRDBWritableObject for Ticket {
         let irdb_paths = get_or_create_irdb_dirs()?;
         Ok(irdb_paths.tickets)
     }
+    fn describe(&self) -> String {
+        format!("{} \"{}\"", self.id(), self.title())
+    }
 }
 
 impl IRDBWritableObject for Project {
@@ -112,14 +352,137 @@ impl IRDBWritableObject for Project {
         let irdb_paths = get_or_create_irdb_dirs()?;
         Ok(irdb_paths.projects)
     }
+    fn describe(&self) -> String {
+        format!("{} \"{}\"", self.id(), self.name())
+    }
+}
+
+impl IRDBWritableObject for Milestone {
+    fn fmt_stub(&self) -> String {
+        self.id().to_string()
+    }
+    fn select_path(&self) -> Result<PathBuf> {
+        let irdb_paths = get_or_create_irdb_dirs()?;
+        Ok(irdb_paths.milestones)
+    }
+    fn describe(&self) -> String {
+        format!("{} \"{}\"", self.id(), self.name())
+    }
+}
+
+impl IRDBWritableObject for Iteration {
+    fn fmt_stub(&self) -> String {
+        self.id().to_string()
+    }
+    fn select_path(&self) -> Result<PathBuf> {
+        let irdb_paths = get_or_create_irdb_dirs()?;
+        Ok(irdb_paths.iterations)
+    }
+    fn describe(&self) -> String {
+        format!("{} \"{}\"", self.id(), self.name())
+    }
+}
+
+impl IRDBWritableObject for Comment {
+    fn fmt_stub(&self) -> String {
+        self.id().to_string()
+    }
+    fn select_path(&self) -> Result<PathBuf> {
+        let irdb_paths = get_or_create_irdb_dirs()?;
+        let ticket_dir = irdb_paths.comments.join(self.ticket().to_string());
+        ensure_dir_exists(&ticket_dir)?;
+        Ok(ticket_dir)
+    }
+    fn describe(&self) -> String {
+        self.ticket().to_string()
+    }
 }
 
 pub(crate) fn verify_and_write<T: IRDBWritableObject>(item: &T) -> Result<()> {
+    write_to(item, &item.select_path()?)?;
+    Ok(())
+}
+
+/// Writes `item` into `dir`, returning the path of the file written.
+fn write_to<T: IRDBWritableObject>(item: &T, dir: &Path) -> Result<PathBuf> {
     let toml_string = toml::to_string(item)?;
     let file_name = format!("{}.toml", item.fmt_stub());
-    let save_path = item.select_path()?;
+    let save_path = dir.join(file_name);
+    if auto_commit_enabled()? {
+        record_write(&save_path, item.describe())?;
+    }
 
-    let mut file = File::create(save_path.join(file_name))?;
+    let mut file = File::create(&save_path)?;
     file.write_all(toml_string.as_bytes())?;
+    Ok(save_path)
+}
+
+/// A file written during this run, kept so it can be committed when the command finishes.
+struct WrittenFile {
+    path: PathBuf,
+    description: String,
+}
+
+static WRITTEN_FILES: Mutex<Vec<WrittenFile>> = Mutex::new(Vec::new());
+
+fn auto_commit_enabled() -> Result<bool> {
+    // Without a command line asking for it, as in tests, nothing is committed.
+    let Some(config) = CONFIG.get() else {
+        return Ok(false);
+    };
+    if !config.commit && !load_settings()?.git.auto_commit {
+        return Ok(false);
+    }
+    // The changes are left for the merge commit instead.
+    Ok(!git_utils::merge_in_progress(env::current_dir()?)?)
+}
+
+/// Deletes a file of the database, recording it for the automatic commit like a write.
+fn remove_file(path: &Path, description: String) -> Result<()> {
+    if auto_commit_enabled()? {
+        record_write(path, description)?;
+    }
+    fs::remove_file(path)?;
     Ok(())
 }
+
+fn record_write(path: &Path, description: String) -> Result<()> {
+    let mut written = WRITTEN_FILES.lock().map_err(|_| "Write log poisoned")?;
+    // Checked before the first write so that a refusal leaves the working tree untouched.
+    if written.is_empty() {
+        let staged = git_utils::staged_paths(path.parent().ok_or("Invalid file path")?)?;
+        if !staged.is_empty() {
+            let staged: Vec<String> = staged.iter().map(ToString::to_string).collect();
+            return Err(From::from(format!(
+                "Refusing to auto-commit while other changes are staged: {}",
+                staged.join(", ")
+            )));
+        }
+    }
+    if !written.iter().any(|file| file.path == path) {
+        written.push(WrittenFile {
+            path: path.to_path_buf(),
+            description,
+        });
+    }
+    Ok(())
+}
+
+/// Commits the files written during this run, if automatic commits are on, with a message like
+/// `repopt: close T0003 "Fix login crash"`. Returns the short commit ID and message.
+pub(crate) fn commit_written_files(command: &str) -> Result<Option<(String, String)>> {
+    let written = std::mem::take(&mut *WRITTEN_FILES.lock().map_err(|_| "Write log poisoned")?);
+    if written.is_empty() {
+        return Ok(None);
+    }
+    let mut descriptions: Vec<&str> = written
+        .iter()
+        .map(|file| file.description.as_str())
+        .collect();
+    // Moving a file writes the new one and removes the old one under the same description.
+    descriptions.dedup();
+    let message = format!("repopt: {command} {}", descriptions.join(", "));
+    let paths: Vec<PathBuf> = written.into_iter().map(|file| file.path).collect();
+    let commit = git_utils::commit_files(&paths, &message)?;
+    Ok(Some((commit.chars().take(7).collect(), message)))
+}
@@ -0,0 +1,411 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::settings::IdStrategy;
+use super::structs::{Comment, InRepoDB, Iteration, Ticket, TicketId};
+use super::{
+    ensure_dir_exists, get_or_create_irdb_dirs, git_utils, load_in_repo_db, remove_file,
+    toml_utils, write_to, IRDBPaths, IRDBWritableObject,
+};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// The revision holding their side of the merge in progress.
+const THEIRS: &str = "MERGE_HEAD";
+
+/// A ticket that had to give up its ID.
+#[derive(Serialize, Debug)]
+pub(crate) struct Renumbering {
+    pub old: TicketId,
+    pub new: TicketId,
+    pub reason: &'static str,
+    /// The other tickets, sprints and comments now pointing at the new ID.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub updated: Vec<String>,
+}
+
+/// Two versions of the database. Since both tickets share the old ID, a reference to it is taken
+/// to mean the moved ticket when it first appears in the later version, along with the ticket.
+struct Change {
+    /// `None` when the change made the first commit.
+    before: Option<String>,
+    /// `None` for the working tree.
+    after: Option<String>,
+}
+
+impl Change {
+    /// Their side of the merge in progress, compared to ours.
+    fn theirs() -> Self {
+        Change {
+            before: Some("HEAD".to_owned()),
+            after: Some(THEIRS.to_owned()),
+        }
+    }
+
+    /// The change that added the file at `path`: the commit that added it, or the working tree
+    /// if it is not committed yet.
+    fn adding(path: &Path) -> Result<Self> {
+        let dir = path.parent().ok_or("Invalid file path")?;
+        if git_utils::file_at_revision(path, "HEAD")?.is_none() {
+            return Ok(Change {
+                before: git_utils::head_commit(dir)?,
+                after: None,
+            });
+        }
+        let added = git_utils::file_history(path)?
+            .into_iter()
+            .take_while(|revision| revision.contents.is_some())
+            .last()
+            .ok_or("File history not found")?;
+        Ok(Change {
+            before: git_utils::first_parent(dir, &added.commit)?,
+            after: Some(added.commit),
+        })
+    }
+}
+
+/// Gives a new ID to every ticket whose ID is also used by another one, along with the
+/// references that came in with it.
+///
+/// During a merge, a ticket file both sides added is split in two: ours keeps the ID and theirs
+/// gets a new one. The fixed files are staged, ready for the merge commit. Outside of merges,
+/// files declaring an ID that another file already uses are moved to a new ID.
+pub(crate) fn renumber(strategy: IdStrategy) -> Result<Vec<Renumbering>> {
+    renumber_in(&get_or_create_irdb_dirs()?, strategy)
+}
+
+fn renumber_in(irdb_paths: &IRDBPaths, strategy: IdStrategy) -> Result<Vec<Renumbering>> {
+    let mut renumberings = Vec::new();
+    let mut touched = Vec::new();
+    let mut theirs_tickets = Vec::new();
+    for (_, ours, theirs) in git_utils::conflicting_additions(&irdb_paths.tickets)? {
+        let ours: Ticket = toml::from_str(&ours)?;
+        let theirs: Ticket = toml::from_str(&theirs)?;
+        write(&ours, &irdb_paths.tickets, &mut touched)?;
+        theirs_tickets.push(theirs);
+    }
+    let in_repo_db = load_in_repo_db(irdb_paths)?;
+    let mut reserved = BTreeSet::new();
+    let mut renamed = BTreeMap::new();
+    for ticket in &theirs_tickets {
+        let new = in_repo_db.new_ticket_id(ticket.get_project_id(), strategy, &reserved);
+        reserved.insert(new.clone());
+        renamed.insert(ticket.id().clone(), new);
+    }
+    for mut ticket in theirs_tickets {
+        ticket.set_id(renamed[ticket.id()].clone());
+        // Whatever their ticket pointed at was on their side too.
+        for (old, new) in &renamed {
+            ticket.replace_references(old, new);
+        }
+        write(&ticket, &irdb_paths.tickets, &mut touched)?;
+    }
+    for (old, new) in &renamed {
+        let updated = update_references(
+            irdb_paths,
+            &in_repo_db,
+            (old, new),
+            &Change::theirs(),
+            |id| renamed.contains_key(id),
+            &mut touched,
+        )?;
+        renumberings.push(Renumbering {
+            old: old.clone(),
+            new: new.clone(),
+            reason: "added on both sides of the merge",
+            updated,
+        });
+    }
+    if !touched.is_empty() {
+        git_utils::stage_files(&touched)?;
+    }
+    renumberings.extend(renumber_duplicates(irdb_paths, strategy, &reserved)?);
+    Ok(renumberings)
+}
+
+/// Moves tickets whose file declares an ID another file already uses. The file named after the
+/// ID keeps it.
+fn renumber_duplicates(
+    irdb_paths: &IRDBPaths,
+    strategy: IdStrategy,
+    reserved: &BTreeSet<TicketId>,
+) -> Result<Vec<Renumbering>> {
+    let mut by_id: BTreeMap<TicketId, Vec<(PathBuf, Ticket)>> = BTreeMap::new();
+    for path in toml_utils::get_toml_files_in_dir(&irdb_paths.tickets)? {
+        let ticket: Ticket = toml::from_str(&fs::read_to_string(&path)?)?;
+        by_id
+            .entry(ticket.id().clone())
+            .or_default()
+            .push((path, ticket));
+    }
+    by_id.retain(|_, files| files.len() > 1);
+    let in_repo_db = load_in_repo_db(irdb_paths)?;
+    let mut reserved = reserved.clone();
+    let mut renumberings = Vec::new();
+    let mut touched = Vec::new();
+    for (id, files) in &by_id {
+        let expected = ticket_path(&irdb_paths.tickets, id);
+        let mut files = files.clone();
+        files.sort_by_key(|(path, _)| *path != expected);
+        for (path, mut ticket) in files.into_iter().skip(1) {
+            let new = in_repo_db.new_ticket_id(ticket.get_project_id(), strategy, &reserved);
+            reserved.insert(new.clone());
+            let change = Change::adding(&path)?;
+            let updated = update_references(
+                irdb_paths,
+                &in_repo_db,
+                (id, &new),
+                &change,
+                |id| by_id.contains_key(id),
+                &mut touched,
+            )?;
+            ticket.set_id(new.clone());
+            write(&ticket, &irdb_paths.tickets, &mut touched)?;
+            if path != ticket_path(&irdb_paths.tickets, &new) {
+                remove_file(&path, ticket.describe())?;
+            }
+            renumberings.push(Renumbering {
+                old: id.clone(),
+                new,
+                reason: "declared by more than one file",
+                updated,
+            });
+        }
+    }
+    Ok(renumberings)
+}
+
+/// Points the references to `old` that came in with `change` at `new`, leaving the tickets
+/// `skip` matches alone. Returns what was updated.
+fn update_references(
+    irdb_paths: &IRDBPaths,
+    in_repo_db: &InRepoDB,
+    (old, new): (&TicketId, &TicketId),
+    change: &Change,
+    skip: impl Fn(&TicketId) -> bool,
+    touched: &mut Vec<PathBuf>,
+) -> Result<Vec<String>> {
+    let mut updated = Vec::new();
+    for ticket in in_repo_db.iter_tickets() {
+        let path = ticket_path(&irdb_paths.tickets, ticket.id());
+        if skip(ticket.id()) || !brought_in(&path, old, Ticket::references, change)? {
+            continue;
+        }
+        let mut ticket = ticket.clone();
+        ticket.replace_references(old, new);
+        updated.push(ticket.describe());
+        write(&ticket, &irdb_paths.tickets, touched)?;
+    }
+    for iteration in in_repo_db.iter_iterations() {
+        let path = irdb_paths
+            .iterations
+            .join(format!("{}.toml", iteration.id()));
+        let contains = |iteration: &Iteration, id: &TicketId| iteration.tickets().contains(id);
+        if !brought_in(&path, old, contains, change)? {
+            continue;
+        }
+        let mut iteration = iteration.clone();
+        iteration.remove_ticket(old);
+        iteration.add_ticket(new.clone());
+        updated.push(iteration.describe());
+        write(&iteration, &irdb_paths.iterations, touched)?;
+    }
+    let comments_dir = irdb_paths.comments.join(old.to_string());
+    if comments_dir.is_dir() {
+        let is_on = |comment: &Comment, id: &TicketId| comment.ticket() == id;
+        for path in toml_utils::get_toml_files_in_dir(&comments_dir)? {
+            if !brought_in(&path, old, is_on, change)? {
+                continue;
+            }
+            let mut comment: Comment = toml::from_str(&fs::read_to_string(&path)?)?;
+            comment.set_ticket(new.clone());
+            updated.push(format!("comment {}", comment.id()));
+            let new_dir = irdb_paths.comments.join(new.to_string());
+            ensure_dir_exists(&new_dir)?;
+            write(&comment, &new_dir, touched)?;
+            remove_file(&path, comment.describe())?;
+            touched.push(path);
+        }
+        // Only succeeds once no comments are left behind, which is all that is wanted.
+        let _ = fs::remove_dir(&comments_dir);
+    }
+    Ok(updated)
+}
+
+fn ticket_path(tickets_dir: &Path, id: &TicketId) -> PathBuf {
+    tickets_dir.join(format!("{id}.toml"))
+}
+
+/// Whether the file at `path` refers to `id` after `change` but did not before.
+fn brought_in<T: DeserializeOwned>(
+    path: &Path,
+    id: &TicketId,
+    refers_to: impl Fn(&T, &TicketId) -> bool,
+    change: &Change,
+) -> Result<bool> {
+    let refers = |contents: Option<String>| {
+        contents
+            .and_then(|contents| toml::from_str(&contents).ok())
+            .is_some_and(|item: T| refers_to(&item, id))
+    };
+    let after = match &change.after {
+        Some(revision) => git_utils::file_at_revision(path, revision)?,
+        None => fs::read_to_string(path).ok(),
+    };
+    let before = match &change.before {
+        Some(revision) => git_utils::file_at_revision(path, revision)?,
+        None => None,
+    };
+    Ok(refers(after) && !refers(before))
+}
+
+fn write<T: IRDBWritableObject>(item: &T, dir: &Path, touched: &mut Vec<PathBuf>) -> Result<()> {
+    touched.push(write_to(item, dir)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::create_irdb_dirs;
+    use super::super::structs::tests::ticket_fixture;
+    use super::super::structs::TicketStatus;
+    use super::super::test_utils::TempDir;
+    use super::*;
+
+    fn ticket(id: &str, title: &str, parent: Option<&str>) -> Ticket {
+        let mut ticket = ticket_fixture(id, title, TicketStatus::Backlog);
+        ticket.set_parent(parent.map(Into::into));
+        ticket
+    }
+
+    fn save<T: Serialize>(path: &Path, item: &T) -> PathBuf {
+        fs::write(path, toml::to_string(item).unwrap()).unwrap();
+        path.to_path_buf()
+    }
+
+    fn load<T: DeserializeOwned>(path: &Path) -> T {
+        toml::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn parent_of(irdb_paths: &IRDBPaths, id: &str) -> Option<TicketId> {
+        let ticket: Ticket = load(&ticket_path(&irdb_paths.tickets, &id.into()));
+        ticket.parent().cloned()
+    }
+
+    #[test]
+    fn test_added_by_theirs() {
+        let repo = TempDir::new_repo("theirs");
+        let child = repo.path().join("T0002.toml");
+        let other = repo.path().join("T0003.toml");
+        let paths = [
+            save(&child, &ticket("T0002", "Child", Some("T0001"))),
+            save(&other, &ticket("T0003", "Other", Some("T0001"))),
+        ];
+        let theirs = git_utils::commit_files(&paths, "theirs").unwrap();
+        save(&child, &ticket("T0002", "Child", None));
+        git_utils::commit_files(&paths, "ours").unwrap();
+        fs::write(repo.path().join(".git").join(THEIRS), format!("{theirs}\n")).unwrap();
+
+        let id = TicketId::from("T0001");
+        let added_by_theirs = |path| brought_in(path, &id, Ticket::references, &Change::theirs());
+        assert!(added_by_theirs(&child).unwrap());
+        // Both sides refer to it, so the reference was there before their ticket.
+        assert!(!added_by_theirs(&other).unwrap());
+    }
+
+    #[test]
+    fn test_duplicate_in_working_tree_takes_new_references() {
+        let repo = TempDir::new_repo("duplicate");
+        let irdb_paths = create_irdb_dirs(repo.path().join(".repopt")).unwrap();
+        let tickets = &irdb_paths.tickets;
+        let committed = [
+            save(
+                &tickets.join("T0001.toml"),
+                &ticket("T0001", "Original", None),
+            ),
+            save(
+                &tickets.join("T0002.toml"),
+                &ticket("T0002", "Old child", Some("T0001")),
+            ),
+        ];
+        git_utils::commit_files(&committed, "base").unwrap();
+        save(&tickets.join("copy.toml"), &ticket("T0001", "Copy", None));
+        save(
+            &tickets.join("T0003.toml"),
+            &ticket("T0003", "New child", Some("T0001")),
+        );
+        let sprint: Iteration = toml::from_str(
+            "id = \"I0001\"\nname = \"Sprint 1\"\nstart = \"2026-01-05\"\nend = \"2026-01-16\"\n\
+             tickets = [\"T0001\"]",
+        )
+        .unwrap();
+        save(&irdb_paths.iterations.join("I0001.toml"), &sprint);
+        let comments_dir = irdb_paths.comments.join("T0001");
+        fs::create_dir(&comments_dir).unwrap();
+        let comment = Comment::new("T0001".into(), None, "Seen on staging".into());
+        save(
+            &comments_dir.join(format!("{}.toml", comment.id())),
+            &comment,
+        );
+
+        let renumberings = renumber_in(&irdb_paths, IdStrategy::Sequential).unwrap();
+
+        assert_eq!(renumberings.len(), 1);
+        let renumbering = &renumberings[0];
+        assert_eq!(renumbering.old, "T0001".into());
+        assert_eq!(renumbering.new, "T0004".into());
+        assert_eq!(renumbering.updated.len(), 3);
+        assert!(!tickets.join("copy.toml").exists());
+        let moved: Ticket = load(&tickets.join("T0004.toml"));
+        assert_eq!(moved.title().to_string(), "Copy");
+        assert_eq!(parent_of(&irdb_paths, "T0002"), Some("T0001".into()));
+        assert_eq!(parent_of(&irdb_paths, "T0003"), Some("T0004".into()));
+        let sprint: Iteration = load(&irdb_paths.iterations.join("I0001.toml"));
+        assert_eq!(sprint.tickets(), &BTreeSet::from(["T0004".into()]));
+        assert!(!comments_dir.exists());
+        let moved_comment: Comment = load(
+            &irdb_paths
+                .comments
+                .join("T0004")
+                .join(format!("{}.toml", comment.id())),
+        );
+        assert_eq!(moved_comment.ticket(), &"T0004".into());
+    }
+
+    #[test]
+    fn test_committed_duplicate_takes_references_from_its_commit() {
+        let repo = TempDir::new_repo("committed-duplicate");
+        let irdb_paths = create_irdb_dirs(repo.path().join(".repopt")).unwrap();
+        let tickets = &irdb_paths.tickets;
+        let base = [
+            save(
+                &tickets.join("T0001.toml"),
+                &ticket("T0001", "Original", None),
+            ),
+            save(
+                &tickets.join("T0002.toml"),
+                &ticket("T0002", "Old child", Some("T0001")),
+            ),
+        ];
+        git_utils::commit_files(&base, "base").unwrap();
+        let added = [
+            save(&tickets.join("copy.toml"), &ticket("T0001", "Copy", None)),
+            save(
+                &tickets.join("T0003.toml"),
+                &ticket("T0003", "New child", Some("T0001")),
+            ),
+        ];
+        git_utils::commit_files(&added, "copy").unwrap();
+
+        let renumberings = renumber_in(&irdb_paths, IdStrategy::Sequential).unwrap();
+
+        assert_eq!(renumberings.len(), 1);
+        assert_eq!(renumberings[0].new, "T0004".into());
+        assert_eq!(parent_of(&irdb_paths, "T0002"), Some("T0001".into()));
+        assert_eq!(parent_of(&irdb_paths, "T0003"), Some("T0004".into()));
+    }
+}
@@ -0,0 +1,136 @@
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use super::structs::Label;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+/// Repository wide settings, read from `config.toml` in the root of the IRDB directory.
+/// Every section is optional so a missing file behaves the same as an empty one.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct Settings {
+    pub labels: LabelSettings,
+    pub estimates: EstimateSettings,
+    pub git: GitSettings,
+    pub hooks: HookSettings,
+    pub branches: BranchSettings,
+    pub ids: IdSettings,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct LabelSettings {
+    pub allowed: Option<BTreeSet<Label>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct GitSettings {
+    /// Commit the files written by every command, as if `--commit` was always passed.
+    pub auto_commit: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct IdSettings {
+    pub strategy: IdStrategy,
+}
+
+/// How new ticket IDs are picked. Sequential IDs are the easiest to read but two branches will
+/// pick the same one; the other strategies make that unlikely enough to work on branches freely.
+/// Tickets of a project with a key use the key instead of `T`, e.g. `API-5` or `API-482913`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Display)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum IdStrategy {
+    /// One more than the highest ID, e.g. `T0005`.
+    #[default]
+    #[display(fmt = "sequential")]
+    Sequential,
+    /// Six random digits, e.g. `T482913`.
+    #[display(fmt = "random")]
+    Random,
+    /// The creation time followed by two random digits, e.g. `T24051514302207`.
+    #[display(fmt = "time-ordered")]
+    TimeOrdered,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(default)]
+pub(crate) struct BranchSettings {
+    /// The name `start` gives a ticket's branch. `{id}`, `{slug}` (the title in lowercase words
+    /// joined by dashes) and `{type}` are replaced.
+    pub pattern: String,
+}
+
+impl Default for BranchSettings {
+    fn default() -> Self {
+        BranchSettings {
+            pattern: "{id}-{slug}".to_owned(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct HookSettings {
+    /// Make the `commit-msg` hook reject messages that reference no ticket at all.
+    pub require_reference: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub(crate) struct EstimateSettings {
+    pub unit: EstimateUnit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Display)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum EstimateUnit {
+    #[default]
+    #[display(fmt = "points")]
+    Points,
+    #[display(fmt = "hours")]
+    Hours,
+}
+
+impl LabelSettings {
+    pub(crate) fn validate<'a>(&self, labels: impl IntoIterator<Item = &'a Label>) -> Result<()> {
+        let Some(allowed) = &self.allowed else {
+            return Ok(());
+        };
+        let unknown: Vec<String> = labels
+            .into_iter()
+            .filter(|label| !allowed.contains(*label))
+            .map(ToString::to_string)
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+        let allowed: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+        Err(From::from(format!(
+            "Unknown label(s): {}. Allowed labels: {}",
+            unknown.join(", "),
+            allowed.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_labels() {
+        let settings: Settings =
+            toml::from_str("[labels]\nallowed = [\"backend\", \"perf\"]").unwrap();
+        assert!(settings.labels.validate(&[Label::from("perf")]).is_ok());
+        assert!(settings.labels.validate(&[Label::from("pref")]).is_err());
+        assert!(Settings::default()
+            .labels
+            .validate(&[Label::from("anything")])
+            .is_ok());
+    }
+}
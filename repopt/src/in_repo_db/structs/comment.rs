@@ -0,0 +1,88 @@
+use super::{TicketId, User};
+use chrono::{DateTime, Utc};
+use derive_more::{Display, From};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+)]
+#[from(forward)]
+pub(crate) struct CommentId(String);
+
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+)]
+#[from(forward)]
+pub(crate) struct CommentBody(String);
+
+// Each comment lives in its own file so that comments added on different branches never touch
+// the same file and always merge cleanly.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Comment {
+    id: CommentId,
+    ticket: TicketId,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<User>,
+    created: DateTime<Utc>,
+    body: CommentBody,
+}
+
+#[allow(dead_code)]
+impl Comment {
+    pub(crate) fn new(ticket: TicketId, author: Option<User>, body: CommentBody) -> Self {
+        let created = Utc::now();
+        let id = Self::generate_id(&ticket, author.as_ref(), &created, &body);
+        Comment {
+            id,
+            ticket,
+            author,
+            created,
+            body,
+        }
+    }
+
+    /// IDs sort chronologically and carry a short content hash so two comments made in the
+    /// same second on different branches still get distinct file names.
+    fn generate_id(
+        ticket: &TicketId,
+        author: Option<&User>,
+        created: &DateTime<Utc>,
+        body: &CommentBody,
+    ) -> CommentId {
+        let mut hasher = DefaultHasher::new();
+        (ticket, author, created, body).hash(&mut hasher);
+        let hash = hasher.finish();
+        format!(
+            "{}-{:08x}",
+            created.format("%Y%m%dT%H%M%SZ"),
+            hash & 0xffff_ffff
+        )
+        .into()
+    }
+
+    pub(crate) fn id(&self) -> &CommentId {
+        &self.id
+    }
+
+    pub(crate) fn ticket(&self) -> &TicketId {
+        &self.ticket
+    }
+
+    pub(crate) fn set_ticket(&mut self, ticket: TicketId) {
+        self.ticket = ticket;
+    }
+
+    pub(crate) fn author(&self) -> Option<&User> {
+        self.author.as_ref()
+    }
+
+    pub(crate) fn created(&self) -> &DateTime<Utc> {
+        &self.created
+    }
+
+    pub(crate) fn body(&self) -> &CommentBody {
+        &self.body
+    }
+}
@@ -0,0 +1,254 @@
+use super::{InRepoDB, LinkType, ProjectId, Ticket, TicketId, TicketStatus};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+/// Diagram formats the graph can be rendered in, besides JSON and YAML.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum GraphOutput {
+    /// Graphviz DOT.
+    Dot,
+    /// Mermaid flowchart.
+    Mermaid,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum NodeKind {
+    Project,
+    Ticket,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum EdgeKind {
+    InProject,
+    Parent,
+    Blocks,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GraphNode {
+    id: String,
+    kind: NodeKind,
+    label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<TicketStatus>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct GraphEdge {
+    from: String,
+    to: String,
+    kind: EdgeKind,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub(crate) struct Graph {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+impl GraphNode {
+    fn fill_colour(&self) -> &'static str {
+        match self.status {
+            None => "#dddddd",
+            Some(TicketStatus::Backlog) => "#ffffff",
+            Some(TicketStatus::InProgress) => "#ffe599",
+            Some(TicketStatus::Closed) => "#b6d7a8",
+        }
+    }
+}
+
+impl Graph {
+    pub(crate) fn to_dot(&self) -> String {
+        let mut out = String::from("digraph repopt {\n    rankdir=LR;\n");
+        for node in &self.nodes {
+            let shape = match node.kind {
+                NodeKind::Project => "folder",
+                NodeKind::Ticket => "box",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\", shape={shape}, style=filled, fillcolor=\"{}\"];",
+                dot_escape(&node.id),
+                dot_escape(&node.label),
+                node.fill_colour()
+            );
+        }
+        for edge in &self.edges {
+            let attrs = match edge.kind {
+                EdgeKind::InProject => "style=dashed, arrowhead=none",
+                EdgeKind::Parent => "label=\"subtask\"",
+                EdgeKind::Blocks => "label=\"blocks\", color=red",
+            };
+            let _ = writeln!(
+                out,
+                "    \"{}\" -> \"{}\" [{attrs}];",
+                dot_escape(&edge.from),
+                dot_escape(&edge.to)
+            );
+        }
+        out.push('}');
+        out
+    }
+
+    pub(crate) fn to_mermaid(&self) -> String {
+        let mut out = String::from("graph LR\n");
+        for node in &self.nodes {
+            let label = mermaid_escape(&node.label);
+            let _ = match node.kind {
+                NodeKind::Project => writeln!(out, "    {}[/\"{label}\"/]", mermaid_id(&node.id)),
+                NodeKind::Ticket => writeln!(out, "    {}[\"{label}\"]", mermaid_id(&node.id)),
+            };
+        }
+        for edge in &self.edges {
+            let arrow = match edge.kind {
+                EdgeKind::InProject => "-.-",
+                EdgeKind::Parent => "-->|subtask|",
+                EdgeKind::Blocks => "==>|blocks|",
+            };
+            let _ = writeln!(
+                out,
+                "    {} {arrow} {}",
+                mermaid_id(&edge.from),
+                mermaid_id(&edge.to)
+            );
+        }
+        for node in &self.nodes {
+            let _ = writeln!(
+                out,
+                "    style {} fill:{}",
+                mermaid_id(&node.id),
+                node.fill_colour()
+            );
+        }
+        out.trim_end().to_owned()
+    }
+}
+
+// Mermaid reads `-` as the start of an edge, so keep node IDs to word characters. Any other
+// character, `_` included, becomes `_<hex code>_`, which keeps distinct IDs apart.
+fn mermaid_id(id: &str) -> String {
+    let mut out = String::with_capacity(id.len());
+    for c in id.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c);
+        } else {
+            let _ = write!(out, "_{:x}_", u32::from(c));
+        }
+    }
+    out
+}
+
+// Inside a quoted DOT string only `\` and `"` are special, and `\n` is a line break.
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Mermaid labels take `#name;` and `#code;` entities, and HTML for line breaks.
+fn mermaid_escape(text: &str) -> String {
+    text.replace('#', "#35;")
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+#[allow(dead_code)]
+impl InRepoDB {
+    /// Builds the graph of projects, tickets and their relationships. With a `query` of a
+    /// project ID only that project's tickets are included; with a ticket ID, only the tickets
+    /// connected to it through parent/child or blocking relationships.
+    pub fn build_graph(&self, query: Option<&str>) -> Result<Graph, String> {
+        let tickets: BTreeSet<&TicketId> = match query {
+            None => self.tickets.keys().collect(),
+            Some(query) if self.projects.contains_key(&ProjectId(query.to_owned())) => self
+                .tickets
+                .values()
+                .filter(|ticket| ticket.get_project_id().0 == query)
+                .map(Ticket::id)
+                .collect(),
+            Some(query) if self.tickets.contains_key(&TicketId(query.to_owned())) => {
+                self.connected_tickets(&TicketId(query.to_owned()))
+            }
+            Some(query) => return Err(format!("No ticket or project matches {query}")),
+        };
+
+        let mut graph = Graph::default();
+        let projects: BTreeSet<&ProjectId> = tickets
+            .iter()
+            .filter_map(|id| self.get_ticket(id))
+            .map(Ticket::get_project_id)
+            .collect();
+        for project_id in projects {
+            let label = self.get_project(project_id).map_or_else(
+                || project_id.to_string(),
+                |p| format!("{project_id}: {}", p.name()),
+            );
+            graph.nodes.push(GraphNode {
+                id: project_id.to_string(),
+                kind: NodeKind::Project,
+                label,
+                status: None,
+            });
+        }
+        for ticket in tickets.iter().filter_map(|id| self.get_ticket(id)) {
+            graph.nodes.push(GraphNode {
+                id: ticket.id().to_string(),
+                kind: NodeKind::Ticket,
+                label: format!("{}: {}", ticket.id(), ticket.title()),
+                status: Some(ticket.status().clone()),
+            });
+            graph.edges.push(GraphEdge {
+                from: ticket.id().to_string(),
+                to: ticket.get_project_id().to_string(),
+                kind: EdgeKind::InProject,
+            });
+            if let Some(parent) = ticket.parent().filter(|parent| tickets.contains(parent)) {
+                graph.edges.push(GraphEdge {
+                    from: parent.to_string(),
+                    to: ticket.id().to_string(),
+                    kind: EdgeKind::Parent,
+                });
+            }
+            for blocked in self.blocks(ticket.id()) {
+                if tickets.contains(blocked) {
+                    graph.edges.push(GraphEdge {
+                        from: ticket.id().to_string(),
+                        to: blocked.to_string(),
+                        kind: EdgeKind::Blocks,
+                    });
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    fn connected_tickets(&self, id: &TicketId) -> BTreeSet<&TicketId> {
+        let mut connected = BTreeSet::new();
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            let Some((current, ticket)) = self.tickets.get_key_value(current) else {
+                continue;
+            };
+            if !connected.insert(current) {
+                continue;
+            }
+            stack.extend(ticket.parent());
+            stack.extend(self.get_children(current).map(Ticket::id));
+            stack.extend(
+                self.get_links(current)
+                    .filter(|link| {
+                        matches!(link.link_type(), LinkType::Blocks | LinkType::BlockedBy)
+                    })
+                    .map(super::TicketLink::target),
+            );
+        }
+        connected
+    }
+}
@@ -0,0 +1,65 @@
+use super::{InRepoDB, Ticket, TicketId};
+use std::collections::BTreeSet;
+
+#[allow(dead_code)]
+impl InRepoDB {
+    pub fn get_children(&self, id: &TicketId) -> impl Iterator<Item = &Ticket> {
+        let id = id.clone();
+        self.tickets
+            .values()
+            .filter(move |ticket| ticket.parent() == Some(&id))
+    }
+
+    pub fn get_descendants(&self, id: &TicketId) -> Vec<&Ticket> {
+        let mut descendants = Vec::new();
+        let mut seen = BTreeSet::from([id]);
+        let mut stack: Vec<&Ticket> = self.get_children(id).collect();
+        while let Some(ticket) = stack.pop() {
+            if !seen.insert(ticket.id()) {
+                continue;
+            }
+            stack.extend(self.get_children(ticket.id()));
+            descendants.push(ticket);
+        }
+        descendants
+    }
+
+    /// Percentage of all descendants that are closed, or `None` for a ticket without children.
+    pub fn get_progress(&self, id: &TicketId) -> Option<u8> {
+        let descendants = self.get_descendants(id);
+        if descendants.is_empty() {
+            return None;
+        }
+        let closed = descendants.iter().filter(|t| !t.is_open()).count();
+        let percent = closed * 100 / descendants.len();
+        Some(u8::try_from(percent).unwrap_or(100))
+    }
+
+    /// Walks up the parent chain from `id`. Stops (without repeating) if the chain loops.
+    pub fn get_ancestors(&self, id: &TicketId) -> Vec<&TicketId> {
+        let mut ancestors = Vec::new();
+        let mut seen = BTreeSet::from([id]);
+        let mut current = self.get_ticket(id).and_then(Ticket::parent);
+        while let Some(parent) = current {
+            ancestors.push(parent);
+            if !seen.insert(parent) {
+                break;
+            }
+            current = self.get_ticket(parent).and_then(Ticket::parent);
+        }
+        ancestors
+    }
+
+    /// True if making `parent` the parent of `child` would make `child` its own ancestor.
+    pub fn would_create_parent_cycle(&self, child: &TicketId, parent: &TicketId) -> bool {
+        parent == child || self.get_ancestors(parent).contains(&child)
+    }
+
+    pub fn find_parent_cycles(&self) -> Vec<TicketId> {
+        self.tickets
+            .keys()
+            .filter(|id| self.get_ancestors(id).contains(id))
+            .cloned()
+            .collect()
+    }
+}
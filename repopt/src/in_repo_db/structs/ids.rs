@@ -0,0 +1,162 @@
+use super::{IterationId, MilestoneId, ProjectId, TicketId};
+use std::cmp::Ordering;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+// IDs are a prefix followed by a number of any width, e.g. `T0042`, `P0001` or `TX-1`.
+
+/// Splits `id` into its prefix and the digits it ends with, if it ends with any.
+pub(crate) fn split_id(id: &str) -> (&str, Option<&str>) {
+    let prefix = id.trim_end_matches(|c: char| c.is_ascii_digit());
+    match &id[prefix.len()..] {
+        "" => (prefix, None),
+        digits => (prefix, Some(digits)),
+    }
+}
+
+/// Orders by prefix, then by number so `T10000` follows `T9999`. IDs that only differ in zero
+/// padding fall back to their text, which keeps the order consistent with equality.
+pub(crate) fn compare_ids(a: &str, b: &str) -> Ordering {
+    let (a_prefix, a_number) = split_id(a);
+    let (b_prefix, b_number) = split_id(b);
+    a_prefix
+        .cmp(b_prefix)
+        .then_with(|| match (a_number, b_number) {
+            (Some(a_number), Some(b_number)) => compare_numbers(a_number, b_number),
+            (a_number, b_number) => a_number.is_some().cmp(&b_number.is_some()),
+        })
+        .then_with(|| a.cmp(b))
+}
+
+fn compare_numbers(a: &str, b: &str) -> Ordering {
+    let a = a.trim_start_matches('0');
+    let b = b.trim_start_matches('0');
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// The ID after the highest `{prefix}{number}` in `ids`, padded like it to at least `width`
+/// digits. IDs with another prefix or without a number, like hand-made ones, are ignored.
+pub(crate) fn next_id<'a>(
+    prefix: &str,
+    width: usize,
+    ids: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let highest = ids
+        .into_iter()
+        .filter_map(|id| match split_id(id) {
+            (id_prefix, Some(number)) if id_prefix == prefix => Some(number),
+            _ => None,
+        })
+        .max_by(|a, b| compare_numbers(a, b));
+    match highest {
+        Some(number) => format!("{prefix}{}", increment(number)),
+        None => format!("{prefix}{:0width$}", 1),
+    }
+}
+
+/// Adds one to a string of digits, keeping its width unless it carries over.
+fn increment(number: &str) -> String {
+    let mut digits = number.as_bytes().to_vec();
+    for digit in digits.iter_mut().rev() {
+        if *digit == b'9' {
+            *digit = b'0';
+        } else {
+            *digit += 1;
+            return String::from_utf8(digits).unwrap_or_default();
+        }
+    }
+    format!("1{}", String::from_utf8(digits).unwrap_or_default())
+}
+
+/// Rejects IDs that could not have come from a file name in the database, e.g. `../T1`.
+pub(crate) fn validate_id(kind: &str, id: &str) -> Result<()> {
+    if id.is_empty() {
+        return Err(From::from(format!("The {kind} ID is empty.")));
+    }
+    if !id
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(From::from(format!(
+            "Invalid {kind} ID '{id}': IDs may only contain letters, digits, '-' and '_'."
+        )));
+    }
+    Ok(())
+}
+
+const MAX_KEY_LENGTH: usize = 10;
+
+/// Checks a project key, the uppercase prefix of Jira-style ticket IDs like `API-12`.
+pub(crate) fn validate_key(key: &str) -> Result<()> {
+    let mut chars = key.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_uppercase())
+        && chars.all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && key.len() <= MAX_KEY_LENGTH;
+    if !valid {
+        return Err(From::from(format!(
+            "Invalid project key '{key}': keys are up to {MAX_KEY_LENGTH} uppercase letters and \
+             digits, starting with a letter."
+        )));
+    }
+    Ok(())
+}
+
+macro_rules! numeric_id_ordering {
+    ($($id:ty),*) => {$(
+        impl Ord for $id {
+            fn cmp(&self, other: &Self) -> Ordering {
+                compare_ids(&self.0, &other.0)
+            }
+        }
+
+        impl PartialOrd for $id {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+    )*};
+}
+
+numeric_id_ordering!(TicketId, ProjectId, MilestoneId, IterationId);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn test_numeric_ordering() {
+        let ids: BTreeSet<TicketId> = ["T10000", "T9999", "T0002", "TX-1", "T01", "T1", "T"]
+            .into_iter()
+            .map(TicketId::from)
+            .collect();
+        let ids: Vec<&str> = ids.iter().map(|id| id.0.as_str()).collect();
+        assert_eq!(ids, ["T", "T01", "T1", "T0002", "T9999", "T10000", "TX-1"]);
+    }
+
+    #[test]
+    fn test_next_id() {
+        assert_eq!(next_id("T", 4, ["T0009", "T0002"]), "T0010");
+        assert_eq!(next_id("T", 4, ["T9999", "TX-1", "T"]), "T10000");
+        assert_eq!(next_id("T", 4, ["T65535"]), "T65536");
+        assert_eq!(next_id("T", 4, ["TX-1"]), "T0001");
+        assert_eq!(next_id("API-", 1, ["T0009", "WEB-4"]), "API-1");
+        assert_eq!(next_id("API-", 1, ["API-9", "API-10"]), "API-11");
+        assert_eq!(
+            next_id("T", 4, ["T99999999999999999999999"]),
+            "T100000000000000000000000"
+        );
+    }
+
+    #[test]
+    fn test_validate_id() {
+        assert!(validate_id("ticket", "TX-1").is_ok());
+        assert!(validate_id("ticket", "../T0001").is_err());
+        assert!(validate_id("ticket", "").is_err());
+        assert!(validate_key("API2").is_ok());
+        assert!(validate_key("api").is_err());
+        assert!(validate_key("2API").is_err());
+        assert!(validate_key("A-PI").is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use super::TicketId;
+use chrono::NaiveDate;
+use derive_more::{Display, From};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use typed_builder::TypedBuilder;
+
+// Ordered numerically, see `ids`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From)]
+#[from(forward)]
+pub(crate) struct IterationId(pub String);
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+)]
+#[from(forward)]
+pub(crate) struct IterationName(String);
+
+// Tickets stay listed on every sprint they were committed to, so carrying a ticket over adds
+// it to the next sprint without rewriting what the previous sprint committed to.
+#[derive(TypedBuilder)]
+#[allow(dead_code)]
+#[derive(Serialize, Deserialize, Debug, Clone, Display)]
+#[display(fmt = "Sprint ID: {id}\nName: {name}\nStart: {start}\nEnd: {end}")]
+pub(crate) struct Iteration {
+    id: IterationId,
+    name: IterationName,
+    start: NaiveDate,
+    end: NaiveDate,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    capacity: Option<u32>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    tickets: BTreeSet<TicketId>,
+    // Other fields...
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[allow(dead_code)]
+impl Iteration {
+    pub(crate) fn id(&self) -> &IterationId {
+        &self.id
+    }
+
+    pub(crate) fn name(&self) -> &IterationName {
+        &self.name
+    }
+
+    pub(crate) fn start(&self) -> NaiveDate {
+        self.start
+    }
+
+    pub(crate) fn end(&self) -> NaiveDate {
+        self.end
+    }
+
+    pub(crate) fn contains_date(&self, date: NaiveDate) -> bool {
+        self.start <= date && date <= self.end
+    }
+
+    pub(crate) fn capacity(&self) -> Option<u32> {
+        self.capacity
+    }
+
+    pub(crate) fn tickets(&self) -> &BTreeSet<TicketId> {
+        &self.tickets
+    }
+
+    pub(crate) fn add_ticket(&mut self, ticket: TicketId) -> bool {
+        self.tickets.insert(ticket)
+    }
+
+    pub(crate) fn remove_ticket(&mut self, ticket: &TicketId) -> bool {
+        self.tickets.remove(ticket)
+    }
+
+    pub(crate) fn is_over_capacity(&self) -> bool {
+        self.capacity
+            .is_some_and(|capacity| self.tickets.len() > capacity as usize)
+    }
+}
@@ -0,0 +1,177 @@
+use super::{InRepoDB, Ticket, TicketId};
+use clap::ValueEnum;
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(
+    Display,
+    Serialize,
+    Deserialize,
+    Debug,
+    PartialEq,
+    Eq,
+    ValueEnum,
+    Clone,
+    Copy,
+    Hash,
+    PartialOrd,
+    Ord,
+)]
+pub(crate) enum LinkType {
+    #[display(fmt = "blocks")]
+    Blocks,
+    #[display(fmt = "blocked by")]
+    BlockedBy,
+    #[display(fmt = "duplicates")]
+    Duplicates,
+    #[display(fmt = "duplicated by")]
+    DuplicatedBy,
+    #[display(fmt = "relates to")]
+    RelatesTo,
+}
+
+impl LinkType {
+    pub(crate) fn inverse(self) -> LinkType {
+        match self {
+            LinkType::Blocks => LinkType::BlockedBy,
+            LinkType::BlockedBy => LinkType::Blocks,
+            LinkType::Duplicates => LinkType::DuplicatedBy,
+            LinkType::DuplicatedBy => LinkType::Duplicates,
+            LinkType::RelatesTo => LinkType::RelatesTo,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Display)]
+#[display(fmt = "{link_type} {target}")]
+pub(crate) struct TicketLink {
+    #[serde(rename = "type")]
+    link_type: LinkType,
+    target: TicketId,
+}
+
+#[allow(dead_code)]
+impl TicketLink {
+    pub(crate) fn new(link_type: LinkType, target: TicketId) -> Self {
+        TicketLink { link_type, target }
+    }
+
+    pub(crate) fn link_type(&self) -> LinkType {
+        self.link_type
+    }
+
+    pub(crate) fn target(&self) -> &TicketId {
+        &self.target
+    }
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DanglingLink {
+    pub source: TicketId,
+    pub link: TicketLink,
+}
+
+/// Expand the links stored on each ticket into a symmetric index, so `T0002 blocks T0005`
+/// stored on `T0002` is also visible as `T0005 blocked by T0002`.
+pub(super) fn resolve_links(
+    tickets: &BTreeMap<TicketId, Ticket>,
+) -> BTreeMap<TicketId, BTreeSet<TicketLink>> {
+    let mut resolved: BTreeMap<TicketId, BTreeSet<TicketLink>> = BTreeMap::new();
+    for ticket in tickets.values() {
+        for link in ticket.links() {
+            resolved
+                .entry(ticket.id().clone())
+                .or_default()
+                .insert(link.clone());
+            resolved
+                .entry(link.target.clone())
+                .or_default()
+                .insert(TicketLink::new(
+                    link.link_type.inverse(),
+                    ticket.id().clone(),
+                ));
+        }
+    }
+    resolved
+}
+
+#[allow(dead_code)]
+impl InRepoDB {
+    pub fn get_links(&self, id: &TicketId) -> impl Iterator<Item = &TicketLink> {
+        self.links.get(id).into_iter().flatten()
+    }
+
+    pub(super) fn blocks(&self, id: &TicketId) -> impl Iterator<Item = &TicketId> {
+        self.get_links(id)
+            .filter(|link| link.link_type == LinkType::Blocks)
+            .map(TicketLink::target)
+    }
+
+    /// Open tickets that must be finished before `id` can progress.
+    pub fn open_blockers(&self, id: &TicketId) -> impl Iterator<Item = &Ticket> {
+        self.get_links(id)
+            .filter(|link| link.link_type == LinkType::BlockedBy)
+            .filter_map(|link| self.get_ticket(&link.target))
+            .filter(|ticket| ticket.is_open())
+    }
+
+    pub fn is_blocked(&self, id: &TicketId) -> bool {
+        self.open_blockers(id).next().is_some()
+    }
+
+    /// Returns the chain `to -> ... -> from` if `from blocks to` would close a cycle.
+    pub fn find_blocking_path(&self, from: &TicketId, to: &TicketId) -> Option<Vec<TicketId>> {
+        let mut stack = vec![vec![to.clone()]];
+        let mut seen = BTreeSet::new();
+        while let Some(path) = stack.pop() {
+            let current = path.last()?;
+            if current == from {
+                return Some(path);
+            }
+            if !seen.insert(current.clone()) {
+                continue;
+            }
+            for next in self.blocks(current) {
+                let mut next_path = path.clone();
+                next_path.push(next.clone());
+                stack.push(next_path);
+            }
+        }
+        None
+    }
+
+    pub fn find_blocking_cycles(&self) -> Vec<Vec<TicketId>> {
+        let mut cycles: Vec<Vec<TicketId>> = Vec::new();
+        for id in self.links.keys() {
+            let already_reported = cycles.iter().any(|cycle| cycle.contains(id));
+            if already_reported {
+                continue;
+            }
+            let cycle = self
+                .blocks(id)
+                .find_map(|next| self.find_blocking_path(id, next));
+            if let Some(mut cycle) = cycle {
+                cycle.insert(0, id.clone());
+                cycles.push(cycle);
+            }
+        }
+        cycles
+    }
+
+    pub fn dangling_links(&self) -> Vec<DanglingLink> {
+        self.tickets
+            .values()
+            .flat_map(|ticket| {
+                ticket
+                    .links()
+                    .iter()
+                    .filter(|link| !self.tickets.contains_key(&link.target))
+                    .map(|link| DanglingLink {
+                        source: ticket.id().clone(),
+                        link: link.clone(),
+                    })
+            })
+            .collect()
+    }
+}
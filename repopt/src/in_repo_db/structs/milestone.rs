@@ -0,0 +1,70 @@
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use derive_more::{Display, From};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use typed_builder::TypedBuilder;
+
+// Ordered numerically, see `ids`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From)]
+#[from(forward)]
+pub(crate) struct MilestoneId(pub String);
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+)]
+#[from(forward)]
+pub(crate) struct MilestoneName(String);
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+)]
+#[from(forward)]
+pub(crate) struct MilestoneDescription(String);
+
+#[derive(Display, Serialize, Deserialize, Debug, PartialEq, Eq, ValueEnum, Clone, Copy)]
+pub(crate) enum MilestoneStatus {
+    #[display(fmt = "Open")]
+    Open,
+    #[display(fmt = "Closed")]
+    Closed,
+}
+
+#[derive(TypedBuilder)]
+#[allow(dead_code)]
+#[derive(Serialize, Deserialize, Debug, Clone, Display)]
+#[display(fmt = "Milestone ID: {id}\nName: {name}\nDescription: {description}\nStatus: {status}")]
+pub(crate) struct Milestone {
+    id: MilestoneId,
+    name: MilestoneName,
+    description: MilestoneDescription,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<NaiveDate>,
+    status: MilestoneStatus,
+    // Other fields...
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[allow(dead_code)]
+impl Milestone {
+    pub(crate) fn id(&self) -> &MilestoneId {
+        &self.id
+    }
+
+    pub(crate) fn name(&self) -> &MilestoneName {
+        &self.name
+    }
+
+    pub(crate) fn due(&self) -> Option<NaiveDate> {
+        self.due
+    }
+
+    pub(crate) fn status(&self) -> MilestoneStatus {
+        self.status
+    }
+
+    pub(crate) fn set_status(&mut self, status: MilestoneStatus) {
+        self.status = status;
+    }
+}
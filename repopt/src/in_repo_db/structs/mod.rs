@@ -1,58 +1,305 @@
+pub(crate) mod comment;
+pub(crate) mod graph;
+pub(crate) mod hierarchy;
+pub(crate) mod ids;
+pub(crate) mod iteration;
+pub(crate) mod links;
+pub(crate) mod milestone;
 pub(crate) mod project;
 pub(crate) mod ticket;
+pub(crate) mod user;
+pub(crate) mod worklog;
 
+use super::settings::IdStrategy;
+use chrono::{NaiveDate, Utc};
+pub(crate) use comment::{Comment, CommentBody};
+pub(crate) use graph::GraphOutput;
+pub(crate) use iteration::{Iteration, IterationId};
+pub(crate) use links::{DanglingLink, LinkType, TicketLink};
+pub(crate) use milestone::{
+    Milestone, MilestoneDescription, MilestoneId, MilestoneName, MilestoneStatus,
+};
 pub(crate) use project::{Project, ProjectDescription, ProjectId, ProjectName};
-use serde::Deserialize;
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 pub(crate) use ticket::{
-    ProjectStub, Ticket, TicketDescription, TicketFilters, TicketId, TicketStatus, TicketTitle,
-    TicketType,
+    Label, ProjectStub, Ticket, TicketDescription, TicketFilters, TicketId, TicketPriority,
+    TicketSeverity, TicketSortOrder, TicketStatus, TicketTitle, TicketType,
 };
+pub(crate) use user::User;
+pub(crate) use worklog::{RunningTimer, WorkDuration, WorkLogEntry};
 
 // ###### InRepoDB Section ######
 #[derive(Deserialize, Debug)]
 pub(crate) struct InRepoDB {
     projects: BTreeMap<ProjectId, Project>,
+    milestones: BTreeMap<MilestoneId, Milestone>,
+    iterations: BTreeMap<IterationId, Iteration>,
     tickets: BTreeMap<TicketId, Ticket>,
+    comments: BTreeMap<TicketId, Vec<Comment>>,
+    #[serde(skip)]
+    links: BTreeMap<TicketId, BTreeSet<TicketLink>>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct BlockedTicket<'a> {
+    ticket: &'a TicketId,
+    blocked_by: Vec<&'a TicketId>,
+}
+
+#[derive(Serialize, Debug)]
+pub(crate) struct MilestoneReport<'a> {
+    #[serde(flatten)]
+    milestone: &'a Milestone,
+    total_tickets: usize,
+    closed_tickets: usize,
+    progress: u8,
+    open_tickets: Vec<&'a TicketId>,
+    open_blockers: Vec<BlockedTicket<'a>>,
+}
+
+/// A ticket together with everything that hangs off it, as rendered by `show`.
+#[derive(Serialize, Debug)]
+pub(crate) struct TicketDetails<'a> {
+    #[serde(flatten)]
+    ticket: &'a Ticket,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    children: Vec<&'a TicketId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    progress: Option<u8>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    linked: Vec<&'a TicketLink>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    comments: &'a [Comment],
 }
 
 #[allow(dead_code)]
 impl InRepoDB {
     pub fn new(
         projects: BTreeMap<ProjectId, Project>,
+        milestones: BTreeMap<MilestoneId, Milestone>,
+        iterations: BTreeMap<IterationId, Iteration>,
         tickets: BTreeMap<TicketId, Ticket>,
+        mut comments: BTreeMap<TicketId, Vec<Comment>>,
     ) -> Self {
-        InRepoDB { projects, tickets }
+        for ticket_comments in comments.values_mut() {
+            ticket_comments.sort_by(|a, b| {
+                a.created()
+                    .cmp(b.created())
+                    .then_with(|| a.id().cmp(b.id()))
+            });
+        }
+        let links = links::resolve_links(&tickets);
+        InRepoDB {
+            projects,
+            milestones,
+            iterations,
+            tickets,
+            comments,
+            links,
+        }
     }
 
     pub fn get_project(&self, id: &ProjectId) -> Option<&Project> {
         self.projects.get(id)
     }
 
+    /// The project with the ID or key `query`, ignoring case.
+    pub fn find_project(&self, query: &str) -> Option<&Project> {
+        self.projects.values().find(|project| {
+            project.id().0.eq_ignore_ascii_case(query)
+                || project
+                    .key()
+                    .is_some_and(|key| key.eq_ignore_ascii_case(query))
+        })
+    }
+
+    /// Whether `id` is in one of the formats this database hands out: `T` followed by digits,
+    /// or a project key, a dash and digits. Other words like `UTF-8` only look like IDs.
+    pub fn is_ticket_id_format(&self, id: &TicketId) -> bool {
+        match ids::split_id(&id.0) {
+            ("T", Some(_)) => true,
+            (prefix, Some(_)) => prefix.strip_suffix('-').is_some_and(|key| {
+                self.projects
+                    .values()
+                    .any(|project| project.key() == Some(key))
+            }),
+            (_, None) => false,
+        }
+    }
+
     pub fn iter_projects(&self) -> impl Iterator<Item = &Project> {
         self.projects.values()
     }
 
+    pub fn get_milestone(&self, id: &MilestoneId) -> Option<&Milestone> {
+        self.milestones.get(id)
+    }
+
+    pub fn iter_milestones(&self) -> impl Iterator<Item = &Milestone> {
+        self.milestones.values()
+    }
+
+    pub fn get_iteration(&self, id: &IterationId) -> Option<&Iteration> {
+        self.iterations.get(id)
+    }
+
+    pub fn iter_iterations(&self) -> impl Iterator<Item = &Iteration> {
+        self.iterations.values()
+    }
+
+    /// The sprint running on `date`; if sprints overlap, the one that started last.
+    pub fn get_iteration_on(&self, date: NaiveDate) -> Option<&Iteration> {
+        self.iterations
+            .values()
+            .filter(|iteration| iteration.contains_date(date))
+            .max_by_key(|iteration| iteration.start())
+    }
+
+    /// The first sprint starting after `id` ends.
+    pub fn get_next_iteration(&self, id: &IterationId) -> Option<&Iteration> {
+        let current = self.get_iteration(id)?;
+        self.iterations
+            .values()
+            .filter(|iteration| iteration.start() > current.end())
+            .min_by_key(|iteration| iteration.start())
+    }
+
+    pub fn get_milestone_report(&self, id: &MilestoneId) -> Option<MilestoneReport<'_>> {
+        let milestone = self.get_milestone(id)?;
+        let tickets: Vec<&Ticket> = self.iter_tickets().in_milestone(id.clone()).collect();
+        let closed = tickets.iter().filter(|ticket| !ticket.is_open()).count();
+        let progress = (closed * 100).checked_div(tickets.len()).unwrap_or(100);
+        let open_blockers = tickets
+            .iter()
+            .filter(|ticket| ticket.is_open())
+            .filter_map(|ticket| {
+                let blockers: Vec<&TicketId> =
+                    self.open_blockers(ticket.id()).map(Ticket::id).collect();
+                (!blockers.is_empty()).then(|| BlockedTicket {
+                    ticket: ticket.id(),
+                    blocked_by: blockers,
+                })
+            })
+            .collect();
+        Some(MilestoneReport {
+            milestone,
+            total_tickets: tickets.len(),
+            closed_tickets: closed,
+            progress: u8::try_from(progress).unwrap_or(100),
+            open_tickets: tickets
+                .iter()
+                .filter(|ticket| ticket.is_open())
+                .map(|ticket| ticket.id())
+                .collect(),
+            open_blockers,
+        })
+    }
+
     pub fn get_ticket(&self, id: &TicketId) -> Option<&Ticket> {
         self.tickets.get(id)
     }
 
+    /// The ticket with the ID `id`, falling back to ignoring case so `api-12` finds `API-12`.
+    pub fn find_ticket(&self, id: &TicketId) -> Option<&Ticket> {
+        self.tickets.get(id).or_else(|| {
+            self.tickets
+                .values()
+                .find(|ticket| ticket.id().0.eq_ignore_ascii_case(&id.0))
+        })
+    }
+
     pub fn iter_tickets(&self) -> impl Iterator<Item = &Ticket> {
         self.tickets.values()
     }
 
-    pub fn get_next_ticket_id(&self) -> Option<TicketId> {
-        let last_id = self.tickets.last_key_value().map(|(id, _)| id)?;
-        let next_id = last_id.to_string().get(1..)?.parse::<u16>().unwrap() + 1;
-        Some(format!("T{next_id:04}").into())
+    pub fn get_comments(&self, id: &TicketId) -> &[Comment] {
+        self.comments.get(id).map_or(&[], Vec::as_slice)
     }
 
-    pub fn get_next_project_id(&self) -> Option<ProjectId> {
-        let last_id = self.projects.last_key_value().map(|(id, _)| id)?;
-        let next_id = last_id.to_string().get(1..)?.parse::<u16>().unwrap() + 1;
-        Some(ProjectId(format!("P{next_id:04}")))
+    pub fn get_ticket_details(&self, id: &TicketId) -> Option<TicketDetails<'_>> {
+        let ticket = self.get_ticket(id)?;
+        Some(TicketDetails {
+            ticket,
+            children: self.get_children(id).map(Ticket::id).collect(),
+            progress: self.get_progress(id),
+            linked: self.get_links(id).collect(),
+            comments: self.get_comments(id),
+        })
     }
+
+    pub fn label_counts(&self) -> BTreeMap<&Label, usize> {
+        self.tickets
+            .values()
+            .flat_map(Ticket::labels)
+            .fold(BTreeMap::new(), |mut counts, label| {
+                *counts.entry(label).or_insert(0) += 1;
+                counts
+            })
+    }
+
+    /// A ticket ID that is neither in use nor `reserved`, picked according to `strategy`.
+    /// Projects with a key number their tickets on their own, like `API-12`.
+    pub fn new_ticket_id(
+        &self,
+        project: &ProjectId,
+        strategy: IdStrategy,
+        reserved: &BTreeSet<TicketId>,
+    ) -> TicketId {
+        let (prefix, width) = match self.get_project(project).and_then(Project::key) {
+            Some(key) => (format!("{key}-"), 1),
+            None => ("T".to_owned(), 4),
+        };
+        let taken = |id: &TicketId| self.tickets.contains_key(id) || reserved.contains(id);
+        loop {
+            let id: TicketId = match strategy {
+                IdStrategy::Sequential => ids::next_id(
+                    &prefix,
+                    width,
+                    self.tickets.keys().chain(reserved).map(|id| id.0.as_str()),
+                )
+                .into(),
+                IdStrategy::Random => {
+                    format!("{prefix}{}", 100_000 + random_number() % 900_000).into()
+                }
+                IdStrategy::TimeOrdered => format!(
+                    "{prefix}{}{:02}",
+                    Utc::now().format("%y%m%d%H%M%S"),
+                    random_number() % 100
+                )
+                .into(),
+            };
+            if !taken(&id) {
+                return id;
+            }
+        }
+    }
+
+    pub fn get_next_milestone_id(&self) -> MilestoneId {
+        ids::next_id("M", 4, self.milestones.keys().map(|id| id.0.as_str())).into()
+    }
+
+    pub fn get_next_iteration_id(&self) -> IterationId {
+        ids::next_id("S", 4, self.iterations.keys().map(|id| id.0.as_str())).into()
+    }
+
+    pub fn get_next_project_id(&self) -> ProjectId {
+        ids::next_id("P", 4, self.projects.keys().map(|id| id.0.as_str())).into()
+    }
+}
+
+/// Good enough randomness for picking IDs, without pulling in a dependency for it.
+fn random_number() -> u64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    hasher.finish()
 }
 
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
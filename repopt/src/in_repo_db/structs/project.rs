@@ -4,9 +4,8 @@ use serde_json::Value;
 use std::collections::HashMap;
 use typed_builder::TypedBuilder;
 
-#[derive(
-    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
-)]
+// Ordered numerically, see `ids`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From)]
 #[from(forward)]
 pub(crate) struct ProjectId(pub String);
 #[derive(
@@ -28,6 +27,10 @@ pub(crate) struct Project {
     id: ProjectId,
     name: ProjectName,
     description: ProjectDescription,
+    /// The prefix of this project's ticket IDs, like `API` for `API-12`.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
     // Other fields...
     #[serde(flatten)]
     extra: HashMap<String, Value>,
@@ -41,4 +44,8 @@ impl Project {
     pub(crate) fn name(&self) -> &ProjectName {
         &self.name
     }
+
+    pub(crate) fn key(&self) -> Option<&str> {
+        self.key.as_deref()
+    }
 }
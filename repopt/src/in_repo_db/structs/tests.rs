@@ -2,6 +2,20 @@ use std::collections::HashMap;
 
 use super::*;
 
+/// A bug in project `P0001`, for tests that only care about a few fields of a ticket.
+pub(crate) fn ticket_fixture(id: &str, title: &str, status: TicketStatus) -> Ticket {
+    let project: ProjectStub = toml::from_str("id = \"P0001\"").unwrap();
+    Ticket::builder()
+        .id(id.into())
+        .title(title.into())
+        .description("Test Description".into())
+        .status(status)
+        .ticket_type(TicketType::Bug)
+        .project(project)
+        .extra(HashMap::new())
+        .build()
+}
+
 fn setup_in_repo_db_one() -> InRepoDB {
     let project_id = ProjectId("P0001".to_string());
     let project = Project::builder()
@@ -11,35 +25,117 @@ fn setup_in_repo_db_one() -> InRepoDB {
         .extra(HashMap::new())
         .build();
 
-    let ticket_id = TicketId("T0001".to_string());
+    let ticket = ticket_fixture("T0001", "Test Ticket", TicketStatus::Backlog);
+    let ticket_id = ticket.id().clone();
+
+    InRepoDB::new(
+        BTreeMap::from([(project_id, project)]),
+        BTreeMap::new(),
+        BTreeMap::new(),
+        BTreeMap::from([(ticket_id, ticket)]),
+        BTreeMap::new(),
+    )
+}
+
+#[test]
+fn test_get_next_ticket_id() {
+    let in_repo_db = setup_in_repo_db_one();
+    let next_ticket_id = in_repo_db.new_ticket_id(
+        &ProjectId("P0001".to_string()),
+        IdStrategy::Sequential,
+        &BTreeSet::new(),
+    );
+    assert_eq!(next_ticket_id, TicketId("T0002".to_string()));
+}
+
+#[test]
+fn test_new_ticket_id_strategies() {
+    let in_repo_db = setup_in_repo_db_one();
+    let project = ProjectId("P0001".to_string());
+    let reserved = BTreeSet::from([TicketId("T0002".to_string())]);
+    assert_eq!(
+        in_repo_db.new_ticket_id(&project, IdStrategy::Sequential, &reserved),
+        TicketId("T0003".to_string())
+    );
+    let digits = |id: &TicketId| id.0.strip_prefix('T').unwrap().len();
+    let random = in_repo_db.new_ticket_id(&project, IdStrategy::Random, &reserved);
+    assert_eq!(digits(&random), 6);
+    let time_ordered = in_repo_db.new_ticket_id(&project, IdStrategy::TimeOrdered, &reserved);
+    assert_eq!(digits(&time_ordered), 14);
+    assert!(time_ordered.0[1..].chars().all(|c| c.is_ascii_digit()));
+}
+
+/// `setup_in_repo_db_one` plus project `P0002` keyed `API`, holding ticket `API-9`.
+fn setup_in_repo_db_keyed() -> InRepoDB {
+    let keyed = Project::builder()
+        .id(ProjectId("P0002".to_string()))
+        .name("API".into())
+        .description("Keyed".into())
+        .key(Some("API".to_string()))
+        .extra(HashMap::new())
+        .build();
     let ticket = Ticket::builder()
-        .id(ticket_id.clone())
-        .title("Test Ticket".into())
+        .id(TicketId("API-9".to_string()))
+        .title("Keyed Ticket".into())
         .description("Test Description".into())
         .status(TicketStatus::Backlog)
-        .ticket_type(TicketType::Bug)
-        .project(ProjectStub::from(&project))
+        .ticket_type(TicketType::Feature)
+        .project(ProjectStub::from(&keyed))
         .extra(HashMap::new())
         .build();
+    let mut in_repo_db = setup_in_repo_db_one();
+    in_repo_db.projects.insert(keyed.id().clone(), keyed);
+    in_repo_db.tickets.insert(ticket.id().clone(), ticket);
+    in_repo_db
+}
 
-    InRepoDB {
-        projects: BTreeMap::from([(project_id, project)]),
-        tickets: BTreeMap::from([(ticket_id, ticket)]),
-    }
+#[test]
+fn test_project_keys() {
+    let in_repo_db = setup_in_repo_db_keyed();
+    let reserved = BTreeSet::from([TicketId("API-10".to_string())]);
+    let new_id = |project: &str, strategy| {
+        in_repo_db.new_ticket_id(&ProjectId(project.to_string()), strategy, &reserved)
+    };
+    assert_eq!(new_id("P0002", IdStrategy::Sequential).0, "API-11");
+    assert_eq!(new_id("P0001", IdStrategy::Sequential).0, "T0002");
+    let random = new_id("P0002", IdStrategy::Random);
+    assert_eq!(random.0.strip_prefix("API-").unwrap().len(), 6);
+
+    assert_eq!(in_repo_db.find_project("api").unwrap().id().0, "P0002");
+    assert_eq!(in_repo_db.find_project("p0001").unwrap().id().0, "P0001");
+    assert_eq!(
+        in_repo_db
+            .find_ticket(&TicketId::from("api-9"))
+            .unwrap()
+            .id()
+            .0,
+        "API-9"
+    );
+    assert!(in_repo_db.is_ticket_id_format(&TicketId::from("API-12")));
+    assert!(in_repo_db.is_ticket_id_format(&TicketId::from("T0012")));
+    assert!(!in_repo_db.is_ticket_id_format(&TicketId::from("UTF-8")));
 }
 
 #[test]
-fn test_get_next_ticket_id() {
-    let in_repo_db = setup_in_repo_db_one();
-    let next_ticket_id = in_repo_db.get_next_ticket_id();
-    assert_eq!(next_ticket_id, Some(TicketId("T0002".to_string())));
+fn test_keyed_ids_on_two_branches() {
+    // Both branches start from the same tickets and know nothing of each other's new ones.
+    let in_repo_db = setup_in_repo_db_keyed();
+    let project = ProjectId("P0002".to_string());
+    let on_branch = |strategy| in_repo_db.new_ticket_id(&project, strategy, &BTreeSet::new());
+    assert_eq!(
+        on_branch(IdStrategy::Sequential),
+        on_branch(IdStrategy::Sequential)
+    );
+    let (ours, theirs) = (on_branch(IdStrategy::Random), on_branch(IdStrategy::Random));
+    assert_ne!(ours, theirs);
+    assert!(ours.0.starts_with("API-") && theirs.0.starts_with("API-"));
 }
 
 #[test]
 fn test_get_next_project_id() {
     let in_repo_db = setup_in_repo_db_one();
     let next_project_id = in_repo_db.get_next_project_id();
-    assert_eq!(next_project_id, Some(ProjectId("P0002".to_string())));
+    assert_eq!(next_project_id, ProjectId("P0002".to_string()));
 }
 
 #[test]
@@ -91,3 +187,447 @@ fn test_with_type() {
         .collect();
     assert_eq!(bug_tickets.len(), 1);
 }
+
+#[test]
+fn test_assigned_to() {
+    let mut in_repo_db = setup_in_repo_db_one();
+    let user: User = "Jane Doe <jane@example.com>".parse().unwrap();
+    in_repo_db
+        .tickets
+        .values_mut()
+        .for_each(|ticket| ticket.assign(user.clone()));
+    let assigned: Vec<&Ticket> = in_repo_db
+        .iter_tickets()
+        .assigned_to("Someone Else <JANE@example.com>".parse().unwrap())
+        .collect();
+    assert_eq!(assigned.len(), 1);
+    let not_assigned: Vec<&Ticket> = in_repo_db
+        .iter_tickets()
+        .assigned_to("John Doe".parse().unwrap())
+        .collect();
+    assert_eq!(not_assigned.len(), 0);
+}
+
+#[test]
+fn test_parse_user() {
+    let user: User = "Jane Doe <jane@example.com>".parse().unwrap();
+    assert_eq!(user.name(), "Jane Doe");
+    assert_eq!(user.email(), Some("jane@example.com"));
+    let user: User = "Jane Doe".parse().unwrap();
+    assert_eq!(user.email(), None);
+    assert!("Jane <jane@example.com".parse::<User>().is_err());
+}
+
+#[test]
+fn test_with_labels() {
+    let mut in_repo_db = setup_in_repo_db_one();
+    in_repo_db.tickets.values_mut().for_each(|ticket| {
+        ticket.add_labels(["perf".into(), "backend".into(), "perf".into()]);
+    });
+    let ticket = in_repo_db.iter_tickets().next().unwrap();
+    assert_eq!(
+        ticket.labels().iter().collect::<Vec<_>>(),
+        [&Label::from("backend"), &Label::from("perf")]
+    );
+    let matching: Vec<&Ticket> = in_repo_db
+        .iter_tickets()
+        .with_labels(vec!["perf".into(), "backend".into()])
+        .collect();
+    assert_eq!(matching.len(), 1);
+    let not_matching: Vec<&Ticket> = in_repo_db
+        .iter_tickets()
+        .with_labels(vec![Label::from("frontend")])
+        .collect();
+    assert_eq!(not_matching.len(), 0);
+    assert_eq!(
+        in_repo_db.label_counts().get(&Label::from("perf")),
+        Some(&1)
+    );
+}
+
+#[test]
+fn test_priority_sort_order() {
+    let project = Project::builder()
+        .id(ProjectId("P0001".to_string()))
+        .name("Test Project".into())
+        .description("Test Description".into())
+        .extra(HashMap::new())
+        .build();
+    let ticket = |id: &str, priority: Option<TicketPriority>, severity: Option<TicketSeverity>| {
+        Ticket::builder()
+            .id(id.into())
+            .title("Test Ticket".into())
+            .description("Test Description".into())
+            .status(TicketStatus::Backlog)
+            .ticket_type(TicketType::Bug)
+            .priority(priority)
+            .severity(severity)
+            .project(ProjectStub::from(&project))
+            .extra(HashMap::new())
+            .build()
+    };
+    let mut tickets = [
+        ticket("T0001", None, None),
+        ticket("T0002", Some(TicketPriority::P2), None),
+        ticket(
+            "T0003",
+            Some(TicketPriority::P0),
+            Some(TicketSeverity::Minor),
+        ),
+        ticket(
+            "T0004",
+            Some(TicketPriority::P0),
+            Some(TicketSeverity::Critical),
+        ),
+        ticket(
+            "T0005",
+            Some(TicketPriority::P2),
+            Some(TicketSeverity::Major),
+        ),
+    ];
+    tickets.sort_by(|a, b| TicketSortOrder::Priority.compare(a, b));
+    let ids: Vec<String> = tickets.iter().map(|t| t.id().to_string()).collect();
+    assert_eq!(ids, ["T0004", "T0003", "T0005", "T0002", "T0001"]);
+}
+
+#[test]
+fn test_severity_only_for_bugs() {
+    let mut in_repo_db = setup_in_repo_db_one();
+    let ticket = in_repo_db.tickets.values_mut().next().unwrap();
+    assert!(ticket.set_severity(TicketSeverity::Major).is_ok());
+    ticket.set_ticket_type(TicketType::Feature);
+    assert!(ticket.set_severity(TicketSeverity::Major).is_err());
+}
+
+#[test]
+fn test_comments_in_ticket_details() {
+    let ticket_id = TicketId("T0001".to_string());
+    let comment = |id: &str, created: &str, body: &str| -> Comment {
+        toml::from_str(&format!(
+            "id = \"{id}\"\nticket = \"T0001\"\ncreated = \"{created}\"\nbody = \"{body}\""
+        ))
+        .unwrap()
+    };
+    let first = comment("20240101T100000Z-0000000b", "2024-01-01T10:00:00Z", "First");
+    // Made in the same second, so only the IDs order these two.
+    let second = comment(
+        "20240101T110000Z-0000000a",
+        "2024-01-01T11:00:00Z",
+        "Second",
+    );
+    let third = comment("20240101T110000Z-0000000b", "2024-01-01T11:00:00Z", "Third");
+    let setup = setup_in_repo_db_one();
+    let in_repo_db = InRepoDB::new(
+        setup.projects,
+        setup.milestones,
+        setup.iterations,
+        setup.tickets,
+        BTreeMap::from([(ticket_id.clone(), vec![third, second, first])]),
+    );
+    let details = in_repo_db.get_ticket_details(&ticket_id).unwrap();
+    let json = serde_json::to_value(&details).unwrap();
+    assert_eq!(json["id"], "T0001");
+    assert_eq!(json["comments"][0]["body"], "First");
+    assert_eq!(json["comments"][1]["body"], "Second");
+    assert_eq!(json["comments"][2]["body"], "Third");
+
+    let first = Comment::new(ticket_id.clone(), None, "First".to_string().into());
+    let second = Comment::new(ticket_id, None, "Second".to_string().into());
+    assert_ne!(first.id(), second.id());
+}
+
+fn setup_in_repo_db_with_links(links: &[(&str, LinkType, &str)]) -> InRepoDB {
+    let setup = setup_in_repo_db_one();
+    let mut tickets: BTreeMap<TicketId, Ticket> = (1..=4)
+        .map(|n| {
+            let id = TicketId(format!("T000{n}"));
+            let ticket = Ticket::builder()
+                .id(id.clone())
+                .title(format!("Ticket {n}").into())
+                .description("Test Description".into())
+                .status(TicketStatus::Backlog)
+                .ticket_type(TicketType::Feature)
+                .project(ProjectStub::from(setup.iter_projects().next().unwrap()))
+                .extra(HashMap::new())
+                .build();
+            (id, ticket)
+        })
+        .collect();
+    for (source, link_type, target) in links {
+        tickets
+            .get_mut(&TicketId((*source).to_string()))
+            .unwrap()
+            .add_link(TicketLink::new(*link_type, (*target).into()));
+    }
+    InRepoDB::new(
+        setup.projects,
+        setup.milestones,
+        setup.iterations,
+        tickets,
+        BTreeMap::new(),
+    )
+}
+
+#[test]
+fn test_links_resolve_both_ways() {
+    let in_repo_db = setup_in_repo_db_with_links(&[("T0001", LinkType::Blocks, "T0002")]);
+    let links: Vec<&TicketLink> = in_repo_db.get_links(&"T0002".into()).collect();
+    assert_eq!(
+        links,
+        [&TicketLink::new(LinkType::BlockedBy, "T0001".into())]
+    );
+    let blocked: Vec<&Ticket> = in_repo_db.iter_tickets().blocked(&in_repo_db).collect();
+    assert_eq!(blocked.len(), 1);
+    assert_eq!(blocked[0].id(), &TicketId::from("T0002"));
+    let unblocked = in_repo_db.iter_tickets().unblocked(&in_repo_db).count();
+    assert_eq!(unblocked, 3);
+}
+
+#[test]
+fn test_closed_blocker_does_not_block() {
+    let mut in_repo_db = setup_in_repo_db_with_links(&[("T0002", LinkType::BlockedBy, "T0001")]);
+    assert!(in_repo_db.is_blocked(&"T0002".into()));
+    in_repo_db
+        .tickets
+        .get_mut(&TicketId::from("T0001"))
+        .unwrap()
+        .close(None);
+    assert!(!in_repo_db.is_blocked(&"T0002".into()));
+}
+
+#[test]
+fn test_blocking_cycle_detection() {
+    let in_repo_db = setup_in_repo_db_with_links(&[
+        ("T0001", LinkType::Blocks, "T0002"),
+        ("T0003", LinkType::BlockedBy, "T0002"),
+    ]);
+    // T0003 blocks T0001 would close T0001 -> T0002 -> T0003 -> T0001
+    let path = in_repo_db.find_blocking_path(&"T0003".into(), &"T0001".into());
+    assert_eq!(
+        path,
+        Some(vec!["T0001".into(), "T0002".into(), "T0003".into()])
+    );
+    assert!(in_repo_db
+        .find_blocking_path(&"T0001".into(), &"T0003".into())
+        .is_none());
+    assert!(in_repo_db.find_blocking_cycles().is_empty());
+
+    let in_repo_db = setup_in_repo_db_with_links(&[
+        ("T0001", LinkType::Blocks, "T0002"),
+        ("T0002", LinkType::Blocks, "T0001"),
+    ]);
+    assert_eq!(in_repo_db.find_blocking_cycles().len(), 1);
+}
+
+#[test]
+fn test_dangling_links() {
+    let in_repo_db = setup_in_repo_db_with_links(&[("T0001", LinkType::RelatesTo, "T0009")]);
+    let dangling = in_repo_db.dangling_links();
+    assert_eq!(dangling.len(), 1);
+    assert_eq!(dangling[0].source, TicketId::from("T0001"));
+}
+
+#[test]
+fn test_parent_progress_and_cycles() {
+    let mut in_repo_db = setup_in_repo_db_with_links(&[]);
+    let set_parent = |db: &mut InRepoDB, child: &str, parent: &str| {
+        db.tickets
+            .get_mut(&TicketId::from(child))
+            .unwrap()
+            .set_parent(Some(parent.into()));
+    };
+    set_parent(&mut in_repo_db, "T0002", "T0001");
+    set_parent(&mut in_repo_db, "T0003", "T0001");
+    set_parent(&mut in_repo_db, "T0004", "T0003");
+    in_repo_db
+        .tickets
+        .get_mut(&TicketId::from("T0004"))
+        .unwrap()
+        .close(None);
+
+    let children: Vec<&Ticket> = in_repo_db
+        .iter_tickets()
+        .children_of("T0001".into())
+        .collect();
+    assert_eq!(children.len(), 2);
+    assert_eq!(in_repo_db.get_descendants(&"T0001".into()).len(), 3);
+    assert_eq!(in_repo_db.get_progress(&"T0001".into()), Some(33));
+    assert_eq!(in_repo_db.get_progress(&"T0003".into()), Some(100));
+    assert_eq!(in_repo_db.get_progress(&"T0002".into()), None);
+
+    assert!(in_repo_db.would_create_parent_cycle(&"T0001".into(), &"T0004".into()));
+    assert!(in_repo_db.would_create_parent_cycle(&"T0001".into(), &"T0001".into()));
+    assert!(!in_repo_db.would_create_parent_cycle(&"T0002".into(), &"T0003".into()));
+    assert!(in_repo_db.find_parent_cycles().is_empty());
+
+    set_parent(&mut in_repo_db, "T0001", "T0004");
+    assert_eq!(in_repo_db.find_parent_cycles().len(), 3);
+}
+
+#[test]
+fn test_build_graph() {
+    let mut in_repo_db = setup_in_repo_db_with_links(&[("T0001", LinkType::Blocks, "T0002")]);
+    in_repo_db
+        .tickets
+        .get_mut(&TicketId::from("T0003"))
+        .unwrap()
+        .set_parent(Some("T0002".into()));
+
+    let graph = in_repo_db.build_graph(None).unwrap();
+    let json = serde_json::to_value(&graph).unwrap();
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 5);
+    let dot = graph.to_dot();
+    assert!(dot.contains("\"T0001\" -> \"T0002\" [label=\"blocks\", color=red];"));
+    assert!(dot.contains("\"T0002\" -> \"T0003\" [label=\"subtask\"];"));
+    assert!(graph.to_mermaid().contains("T0001 ==>|blocks| T0002"));
+
+    in_repo_db
+        .tickets
+        .get_mut(&TicketId::from("T0002"))
+        .unwrap()
+        .set_title("Say \"hi\" in C:\\temp\n#2".into());
+    let graph = in_repo_db.build_graph(None).unwrap();
+    assert!(graph
+        .to_dot()
+        .contains("[label=\"T0002: Say \\\"hi\\\" in C:\\\\temp\\n#2\""));
+    assert!(graph
+        .to_mermaid()
+        .contains("T0002[\"T0002: Say #quot;hi#quot; in C:\\temp<br>#35;2\"]"));
+
+    // T0004 is unrelated to T0001 so it is left out of T0001's neighbourhood.
+    let graph = in_repo_db.build_graph(Some("T0001")).unwrap();
+    let json = serde_json::to_value(&graph).unwrap();
+    assert_eq!(json["nodes"].as_array().unwrap().len(), 4);
+    assert!(in_repo_db.build_graph(Some("X0001")).is_err());
+}
+
+#[test]
+fn test_mermaid_ids_are_distinct() {
+    let mut in_repo_db = setup_in_repo_db_keyed();
+    let ticket = ticket_fixture("API_9", "Lookalike", TicketStatus::Backlog);
+    in_repo_db.tickets.insert(ticket.id().clone(), ticket);
+
+    let mermaid = in_repo_db.build_graph(None).unwrap().to_mermaid();
+    assert!(mermaid.contains("API_2d_9[\"API-9: Keyed Ticket\"]"));
+    assert!(mermaid.contains("API_5f_9[\"API_9: Lookalike\"]"));
+}
+
+#[test]
+fn test_milestone_report() {
+    let mut in_repo_db = setup_in_repo_db_with_links(&[("T0004", LinkType::Blocks, "T0002")]);
+    let milestone = Milestone::builder()
+        .id(MilestoneId("M0001".to_string()))
+        .name("Launch".into())
+        .description("First release".into())
+        .status(MilestoneStatus::Open)
+        .extra(HashMap::new())
+        .build();
+    in_repo_db
+        .milestones
+        .insert(milestone.id().clone(), milestone);
+    for (id, closed) in [("T0001", true), ("T0002", false)] {
+        let ticket = in_repo_db.tickets.get_mut(&TicketId::from(id)).unwrap();
+        ticket.set_milestone(Some("M0001".into()));
+        if closed {
+            ticket.close(None);
+        }
+    }
+    assert_eq!(in_repo_db.get_next_milestone_id(), "M0002".into());
+    let report = in_repo_db.get_milestone_report(&"M0001".into()).unwrap();
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["name"], "Launch");
+    assert_eq!(json["total_tickets"], 2);
+    assert_eq!(json["progress"], 50);
+    assert_eq!(json["open_blockers"][0]["ticket"], "T0002");
+    assert_eq!(json["open_blockers"][0]["blocked_by"][0], "T0004");
+}
+
+#[test]
+fn test_iterations() {
+    let mut in_repo_db = setup_in_repo_db_one();
+    let date = |day| chrono::NaiveDate::from_ymd_opt(2024, 1, day).unwrap();
+    for (id, start, end) in [("S0001", 1, 14), ("S0002", 15, 28)] {
+        let iteration = Iteration::builder()
+            .id(id.into())
+            .name(format!("Sprint {id}").into())
+            .start(date(start))
+            .end(date(end))
+            .capacity(Some(1))
+            .extra(HashMap::new())
+            .build();
+        in_repo_db.iterations.insert(id.into(), iteration);
+    }
+    assert_eq!(in_repo_db.get_next_iteration_id(), "S0003".into());
+    assert_eq!(
+        in_repo_db.get_iteration_on(date(14)).map(Iteration::id),
+        Some(&"S0001".into())
+    );
+    assert!(in_repo_db.get_iteration_on(date(29)).is_none());
+    assert_eq!(
+        in_repo_db
+            .get_next_iteration(&"S0001".into())
+            .map(Iteration::id),
+        Some(&"S0002".into())
+    );
+    let sprint = in_repo_db.iterations.get_mut(&"S0001".into()).unwrap();
+    assert!(sprint.add_ticket("T0001".into()));
+    assert!(!sprint.add_ticket("T0001".into()));
+    assert!(!sprint.is_over_capacity());
+    sprint.add_ticket("T0002".into());
+    assert!(sprint.is_over_capacity());
+}
+
+#[test]
+fn test_work_duration_parse_and_display() {
+    let parse = |s: &str| s.parse::<WorkDuration>().map(WorkDuration::minutes);
+    assert_eq!(parse("1h30m"), Ok(90));
+    assert_eq!(parse("2H"), Ok(120));
+    assert_eq!(parse("45"), Ok(45));
+    assert_eq!(parse("1d"), Ok(480));
+    assert!(parse("0").is_err());
+    assert!(parse("1h30").is_err());
+    assert!(parse("1h0").is_err());
+    assert!(parse("0h30").is_err());
+    assert!(parse("soon").is_err());
+    assert_eq!(WorkDuration::from_minutes(90).to_string(), "1h30m");
+    assert_eq!(WorkDuration::from_minutes(120).to_string(), "2h");
+    assert_eq!(WorkDuration::from_minutes(5).to_string(), "5m");
+}
+
+#[test]
+fn test_timesheet() {
+    let mut in_repo_db = setup_in_repo_db_with_links(&[]);
+    let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+    let jane = User::new("Jane".to_owned(), Some("jane@example.com".to_owned()));
+    let john = User::new("John".to_owned(), None);
+    let log = [
+        ("T0001", &jane, 1, "2h"),
+        ("T0001", &jane, 10, "30m"),
+        ("T0002", &john, 10, "1h"),
+    ];
+    for (id, author, day, duration) in log {
+        in_repo_db
+            .tickets
+            .get_mut(&id.into())
+            .unwrap()
+            .log_work(WorkLogEntry::new(
+                Some(author.clone()),
+                date(day),
+                duration.parse().unwrap(),
+                None,
+            ));
+    }
+    let all = in_repo_db.timesheet(None, None);
+    assert_eq!(all.total().to_string(), "3h30m");
+    assert_eq!(
+        all.project_time(&"P0001".into()).map(WorkDuration::minutes),
+        Some(210)
+    );
+    let recent = in_repo_db.timesheet(Some(date(5)), Some(&jane));
+    assert_eq!(recent.total().to_string(), "30m");
+    let tickets: Vec<_> = recent
+        .tickets()
+        .map(|(id, time)| (id.to_string(), time.minutes()))
+        .collect();
+    assert_eq!(tickets, [("T0001".to_owned(), 30)]);
+}
@@ -1,9 +1,10 @@
-use super::{Project, ProjectId};
+use super::{InRepoDB, MilestoneId, Project, ProjectId, TicketLink, User, WorkLogEntry};
 use clap::ValueEnum;
 use derive_more::{Display, From};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use typed_builder::TypedBuilder;
 
 #[derive(Display, Serialize, Deserialize, Debug, PartialEq, Eq, ValueEnum, Clone)]
@@ -34,9 +35,69 @@ pub(crate) enum TicketType {
     Other,
 }
 
+// Variants are declared most urgent first so the derived `Ord` is the triage order.
 #[derive(
-    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+    Display, Serialize, Deserialize, Debug, PartialEq, Eq, ValueEnum, Clone, Copy, PartialOrd, Ord,
 )]
+pub(crate) enum TicketPriority {
+    #[display(fmt = "P0")]
+    #[value(name = "P0", alias = "p0")]
+    P0,
+    #[display(fmt = "P1")]
+    #[value(name = "P1", alias = "p1")]
+    P1,
+    #[display(fmt = "P2")]
+    #[value(name = "P2", alias = "p2")]
+    P2,
+    #[display(fmt = "P3")]
+    #[value(name = "P3", alias = "p3")]
+    P3,
+}
+
+#[derive(
+    Display, Serialize, Deserialize, Debug, PartialEq, Eq, ValueEnum, Clone, Copy, PartialOrd, Ord,
+)]
+pub(crate) enum TicketSeverity {
+    #[display(fmt = "Critical")]
+    Critical,
+    #[display(fmt = "Major")]
+    Major,
+    #[display(fmt = "Minor")]
+    Minor,
+    #[display(fmt = "Trivial")]
+    Trivial,
+}
+
+#[derive(Debug, Clone, Copy, Default, ValueEnum)]
+pub(crate) enum TicketSortOrder {
+    /// Priority first (unprioritised last), then severity, then ID.
+    #[default]
+    Priority,
+    Id,
+}
+
+impl TicketSortOrder {
+    pub(crate) fn compare(self, a: &Ticket, b: &Ticket) -> Ordering {
+        match self {
+            TicketSortOrder::Priority => {
+                // `None` sorts before `Some` so flip it to push untriaged tickets to the end.
+                let key = |t: &Ticket| {
+                    (
+                        t.priority.is_none(),
+                        t.priority,
+                        t.severity.is_none(),
+                        t.severity,
+                    )
+                };
+                key(a).cmp(&key(b)).then_with(|| a.id.cmp(&b.id))
+            }
+            TicketSortOrder::Id => a.id.cmp(&b.id),
+        }
+    }
+}
+
+// Ordered numerically, see `ids`.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From)]
 #[from(forward)]
 pub(crate) struct TicketId(pub String);
 
@@ -52,12 +113,24 @@ pub(crate) struct TicketTitle(String);
 #[from(forward)]
 pub(crate) struct TicketDescription(String);
 
+#[derive(
+    Debug, Deserialize, Serialize, PartialEq, Eq, Clone, Hash, Display, From, PartialOrd, Ord,
+)]
+#[from(forward)]
+pub(crate) struct Label(String);
+
 #[allow(dead_code)]
 #[derive(Serialize, Deserialize, Debug, Display, Clone, PartialEq, Eq)]
 pub(crate) struct ProjectStub {
     id: ProjectId,
 }
 
+impl ProjectStub {
+    pub(crate) fn id(&self) -> &ProjectId {
+        &self.id
+    }
+}
+
 impl From<&Project> for ProjectStub {
     fn from(project: &Project) -> Self {
         ProjectStub {
@@ -78,7 +151,42 @@ pub(crate) struct Ticket {
     status: TicketStatus,
     #[serde(rename = "type")]
     ticket_type: TicketType,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<TicketPriority>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    severity: Option<TicketSeverity>,
+    /// Story points or hours, depending on `estimates.unit` in the settings.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    estimate: Option<u32>,
     project: ProjectStub,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<TicketId>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    milestone: Option<MilestoneId>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reporter: Option<User>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    assignee: Option<User>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    labels: BTreeSet<Label>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    links: BTreeSet<TicketLink>,
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    work_log: Vec<WorkLogEntry>,
+    /// What resolved the ticket when it was closed, e.g. the hash of a `Fixes T0001` commit.
+    #[builder(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    resolution: Option<String>,
     // Other fields...
     #[serde(flatten)]
     extra: HashMap<String, Value>,
@@ -90,10 +198,22 @@ impl Ticket {
         &self.id
     }
 
+    pub(crate) fn set_id(&mut self, id: TicketId) {
+        self.id = id;
+    }
+
     pub(crate) fn title(&self) -> &TicketTitle {
         &self.title
     }
 
+    pub(crate) fn description(&self) -> &TicketDescription {
+        &self.description
+    }
+
+    pub(crate) fn status(&self) -> &TicketStatus {
+        &self.status
+    }
+
     pub(crate) fn is_open(&self) -> bool {
         self.status != TicketStatus::Closed
     }
@@ -102,12 +222,167 @@ impl Ticket {
         &self.project.id
     }
 
-    pub(crate) fn close(&mut self) {
+    pub(crate) fn close(&mut self, resolution: Option<String>) {
         self.status = TicketStatus::Closed;
+        self.resolution = resolution;
     }
 
     pub(crate) fn reopen(&mut self, status: Option<TicketStatus>) {
         self.status = status.unwrap_or(TicketStatus::InProgress);
+        self.resolution = None;
+    }
+
+    pub(crate) fn resolution(&self) -> Option<&str> {
+        self.resolution.as_deref()
+    }
+
+    pub(crate) fn ticket_type(&self) -> &TicketType {
+        &self.ticket_type
+    }
+
+    pub(crate) fn set_title(&mut self, title: TicketTitle) {
+        self.title = title;
+    }
+
+    pub(crate) fn set_description(&mut self, description: TicketDescription) {
+        self.description = description;
+    }
+
+    pub(crate) fn set_status(&mut self, status: TicketStatus) {
+        self.status = status;
+    }
+
+    pub(crate) fn set_ticket_type(&mut self, ticket_type: TicketType) {
+        if ticket_type != TicketType::Bug {
+            self.severity = None;
+        }
+        self.ticket_type = ticket_type;
+    }
+
+    pub(crate) fn priority(&self) -> Option<TicketPriority> {
+        self.priority
+    }
+
+    pub(crate) fn set_priority(&mut self, priority: TicketPriority) {
+        self.priority = Some(priority);
+    }
+
+    pub(crate) fn severity(&self) -> Option<TicketSeverity> {
+        self.severity
+    }
+
+    pub(crate) fn set_severity(&mut self, severity: TicketSeverity) -> Result<(), String> {
+        if self.ticket_type != TicketType::Bug {
+            return Err(format!(
+                "Severity only applies to bugs, {} is a {}",
+                self.id, self.ticket_type
+            ));
+        }
+        self.severity = Some(severity);
+        Ok(())
+    }
+
+    pub(crate) fn estimate(&self) -> Option<u32> {
+        self.estimate
+    }
+
+    pub(crate) fn set_estimate(&mut self, estimate: Option<u32>) {
+        self.estimate = estimate;
+    }
+
+    pub(crate) fn parent(&self) -> Option<&TicketId> {
+        self.parent.as_ref()
+    }
+
+    pub(crate) fn set_parent(&mut self, parent: Option<TicketId>) {
+        self.parent = parent;
+    }
+
+    pub(crate) fn milestone(&self) -> Option<&MilestoneId> {
+        self.milestone.as_ref()
+    }
+
+    pub(crate) fn set_milestone(&mut self, milestone: Option<MilestoneId>) {
+        self.milestone = milestone;
+    }
+
+    pub(crate) fn reporter(&self) -> Option<&User> {
+        self.reporter.as_ref()
+    }
+
+    pub(crate) fn assignee(&self) -> Option<&User> {
+        self.assignee.as_ref()
+    }
+
+    pub(crate) fn assign(&mut self, user: User) {
+        self.assignee = Some(user);
+    }
+
+    pub(crate) fn unassign(&mut self) {
+        self.assignee = None;
+    }
+
+    pub(crate) fn labels(&self) -> &BTreeSet<Label> {
+        &self.labels
+    }
+
+    pub(crate) fn add_labels(&mut self, labels: impl IntoIterator<Item = Label>) {
+        self.labels.extend(labels);
+    }
+
+    pub(crate) fn links(&self) -> &BTreeSet<TicketLink> {
+        &self.links
+    }
+
+    pub(crate) fn add_link(&mut self, link: TicketLink) {
+        self.links.insert(link);
+    }
+
+    /// Removes every stored link pointing at `target`, returning whether any were removed.
+    pub(crate) fn remove_links_to(&mut self, target: &TicketId) -> bool {
+        let before = self.links.len();
+        self.links.retain(|link| link.target() != target);
+        before != self.links.len()
+    }
+
+    /// Whether the parent or any link points at `id`.
+    pub(crate) fn references(&self, id: &TicketId) -> bool {
+        self.parent.as_ref() == Some(id) || self.links.iter().any(|link| link.target() == id)
+    }
+
+    /// Points the parent and links at `new` instead of `old`, returning whether anything changed.
+    pub(crate) fn replace_references(&mut self, old: &TicketId, new: &TicketId) -> bool {
+        if !self.references(old) {
+            return false;
+        }
+        if self.parent.as_ref() == Some(old) {
+            self.parent = Some(new.clone());
+        }
+        self.links = std::mem::take(&mut self.links)
+            .into_iter()
+            .map(|link| {
+                if link.target() == old {
+                    TicketLink::new(link.link_type(), new.clone())
+                } else {
+                    link
+                }
+            })
+            .collect();
+        true
+    }
+
+    pub(crate) fn work_log(&self) -> &[WorkLogEntry] {
+        &self.work_log
+    }
+
+    pub(crate) fn log_work(&mut self, entry: WorkLogEntry) {
+        self.work_log.push(entry);
+    }
+
+    pub(crate) fn remove_labels<'l>(&mut self, labels: impl IntoIterator<Item = &'l Label>) {
+        labels.into_iter().for_each(|label| {
+            self.labels.remove(label);
+        });
     }
 }
 
@@ -130,6 +405,39 @@ where
     fn for_project(self, project: ProjectId) -> Box<dyn Iterator<Item = &'a Ticket> + 'a> {
         Box::new(self.filter(move |ticket| ticket.project.id == project))
     }
+
+    fn assigned_to(self, user: User) -> Box<dyn Iterator<Item = &'a Ticket> + 'a> {
+        Box::new(self.filter(move |ticket| {
+            ticket
+                .assignee
+                .as_ref()
+                .is_some_and(|assignee| assignee.matches(&user))
+        }))
+    }
+
+    fn with_labels<L>(self, labels: L) -> Box<dyn Iterator<Item = &'a Ticket> + 'a>
+    where
+        L: Into<Vec<Label>>,
+    {
+        let labels = labels.into();
+        Box::new(self.filter(move |ticket| labels.iter().all(|l| ticket.labels.contains(l))))
+    }
+
+    fn in_milestone(self, milestone: MilestoneId) -> Box<dyn Iterator<Item = &'a Ticket> + 'a> {
+        Box::new(self.filter(move |ticket| ticket.milestone.as_ref() == Some(&milestone)))
+    }
+
+    fn children_of(self, parent: TicketId) -> Box<dyn Iterator<Item = &'a Ticket> + 'a> {
+        Box::new(self.filter(move |ticket| ticket.parent.as_ref() == Some(&parent)))
+    }
+
+    fn blocked(self, in_repo_db: &'a InRepoDB) -> Box<dyn Iterator<Item = &'a Ticket> + 'a> {
+        Box::new(self.filter(move |ticket| in_repo_db.is_blocked(&ticket.id)))
+    }
+
+    fn unblocked(self, in_repo_db: &'a InRepoDB) -> Box<dyn Iterator<Item = &'a Ticket> + 'a> {
+        Box::new(self.filter(move |ticket| !in_repo_db.is_blocked(&ticket.id)))
+    }
 }
 
 // Implement the trait for all iterators that return a ticket reference with the same lifetime
@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct User {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    email: Option<String>,
+}
+
+#[allow(dead_code)]
+impl User {
+    pub(crate) fn new(name: String, email: Option<String>) -> Self {
+        User { name, email }
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    /// Two users are the same person if their emails match, falling back to the name when
+    /// either side has no email recorded.
+    pub(crate) fn matches(&self, other: &User) -> bool {
+        match (&self.email, &other.email) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            _ => self.name == other.name,
+        }
+    }
+}
+
+impl fmt::Display for User {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.email {
+            Some(email) => write!(f, "{} <{email}>", self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+impl FromStr for User {
+    type Err = String;
+
+    /// Parses either `Name <email>` or a bare `Name`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("User cannot be empty".to_string());
+        }
+        match s.split_once('<') {
+            Some((name, rest)) => {
+                let email = rest
+                    .strip_suffix('>')
+                    .ok_or_else(|| format!("Invalid user: {s}"))?
+                    .trim();
+                Ok(User::new(name.trim().to_string(), Some(email.to_string())))
+            }
+            None => Ok(User::new(s.to_string(), None)),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use super::{InRepoDB, ProjectId, TicketId, User};
+use chrono::{DateTime, NaiveDate, Utc};
+use derive_more::Display;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// A span of logged work, kept to whole minutes and written as e.g. `1h30m`.
+#[derive(
+    Debug, Default, Serialize, Deserialize, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord,
+)]
+#[serde(try_from = "String", into = "String")]
+pub(crate) struct WorkDuration(u32);
+
+impl WorkDuration {
+    pub(crate) fn from_minutes(minutes: u32) -> Self {
+        WorkDuration(minutes)
+    }
+
+    pub(crate) fn minutes(self) -> u32 {
+        self.0
+    }
+}
+
+impl std::ops::Add for WorkDuration {
+    type Output = WorkDuration;
+
+    fn add(self, other: WorkDuration) -> WorkDuration {
+        WorkDuration(self.0.saturating_add(other.0))
+    }
+}
+
+impl std::iter::Sum for WorkDuration {
+    fn sum<I: Iterator<Item = WorkDuration>>(iter: I) -> Self {
+        iter.fold(WorkDuration::default(), |a, b| a + b)
+    }
+}
+
+impl std::fmt::Display for WorkDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.0 / 60, self.0 % 60) {
+            (0, minutes) => write!(f, "{minutes}m"),
+            (hours, 0) => write!(f, "{hours}h"),
+            (hours, minutes) => write!(f, "{hours}h{minutes}m"),
+        }
+    }
+}
+
+impl FromStr for WorkDuration {
+    type Err = String;
+
+    /// Accepts `1h30m`, `2h`, `45m`, `1d` (a working day of eight hours) and bare minutes.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid duration '{s}', expected something like 1h30m");
+        let s = s.trim().to_lowercase();
+        let mut minutes: u32 = 0;
+        let mut number = String::new();
+        for c in s.chars() {
+            if c.is_ascii_digit() {
+                number.push(c);
+                continue;
+            }
+            let unit = match c {
+                'd' => 8 * 60,
+                'h' => 60,
+                'm' => 1,
+                _ => return Err(invalid()),
+            };
+            let value: u32 = std::mem::take(&mut number).parse().map_err(|_| invalid())?;
+            minutes = value
+                .checked_mul(unit)
+                .and_then(|value| minutes.checked_add(value))
+                .ok_or_else(invalid)?;
+        }
+        if !number.is_empty() {
+            // Only a duration that is all digits counts as bare minutes. After a unit, as in
+            // `1h30`, a number without one is a mistake.
+            if s.chars().any(|c| !c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            minutes = number.parse().map_err(|_| invalid())?;
+        }
+        if minutes == 0 {
+            return Err(invalid());
+        }
+        Ok(WorkDuration(minutes))
+    }
+}
+
+impl TryFrom<String> for WorkDuration {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<WorkDuration> for String {
+    fn from(value: WorkDuration) -> Self {
+        value.to_string()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Display)]
+#[display(fmt = "{date} {duration}")]
+pub(crate) struct WorkLogEntry {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<User>,
+    date: NaiveDate,
+    duration: WorkDuration,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+}
+
+#[allow(dead_code)]
+impl WorkLogEntry {
+    pub(crate) fn new(
+        author: Option<User>,
+        date: NaiveDate,
+        duration: WorkDuration,
+        note: Option<String>,
+    ) -> Self {
+        WorkLogEntry {
+            author,
+            date,
+            duration,
+            note,
+        }
+    }
+
+    pub(crate) fn author(&self) -> Option<&User> {
+        self.author.as_ref()
+    }
+
+    pub(crate) fn date(&self) -> NaiveDate {
+        self.date
+    }
+
+    pub(crate) fn duration(&self) -> WorkDuration {
+        self.duration
+    }
+}
+
+/// The timer started by `start-timer`. It is personal, so it lives in the git directory rather
+/// than in the committed database.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RunningTimer {
+    pub ticket: TicketId,
+    pub started: DateTime<Utc>,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct TimesheetTicket<'a> {
+    ticket: &'a TicketId,
+    project: &'a ProjectId,
+    title: String,
+    time: WorkDuration,
+}
+
+#[derive(Serialize, Debug, PartialEq, Eq)]
+pub(crate) struct Timesheet<'a> {
+    since: Option<NaiveDate>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author: Option<&'a User>,
+    total: WorkDuration,
+    projects: BTreeMap<&'a ProjectId, WorkDuration>,
+    tickets: Vec<TimesheetTicket<'a>>,
+}
+
+#[allow(dead_code)]
+impl<'a> Timesheet<'a> {
+    pub(crate) fn total(&self) -> WorkDuration {
+        self.total
+    }
+
+    pub(crate) fn project_time(&self, project: &ProjectId) -> Option<WorkDuration> {
+        self.projects.get(project).copied()
+    }
+
+    pub(crate) fn tickets(&self) -> impl Iterator<Item = (&'a TicketId, WorkDuration)> + '_ {
+        self.tickets.iter().map(|entry| (entry.ticket, entry.time))
+    }
+}
+
+#[allow(dead_code)]
+impl InRepoDB {
+    /// Sums the work logged on or after `since`, optionally only by `author`, per ticket and
+    /// per project.
+    pub fn timesheet<'a>(
+        &'a self,
+        since: Option<NaiveDate>,
+        author: Option<&'a User>,
+    ) -> Timesheet<'a> {
+        let mut timesheet = Timesheet {
+            since,
+            author,
+            total: WorkDuration::default(),
+            projects: BTreeMap::new(),
+            tickets: Vec::new(),
+        };
+        for ticket in self.tickets.values() {
+            let time: WorkDuration = ticket
+                .work_log()
+                .iter()
+                .filter(|entry| since.is_none_or(|since| entry.date >= since))
+                .filter(|entry| {
+                    author.is_none_or(|author| {
+                        entry.author.as_ref().is_some_and(|a| author.matches(a))
+                    })
+                })
+                .map(WorkLogEntry::duration)
+                .sum();
+            if time.minutes() == 0 {
+                continue;
+            }
+            timesheet.total = timesheet.total + time;
+            let project = timesheet
+                .projects
+                .entry(ticket.get_project_id())
+                .or_default();
+            *project = *project + time;
+            timesheet.tickets.push(TimesheetTicket {
+                ticket: ticket.id(),
+                project: ticket.get_project_id(),
+                title: ticket.title().to_string(),
+                time,
+            });
+        }
+        timesheet
+    }
+}
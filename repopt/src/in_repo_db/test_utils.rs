@@ -0,0 +1,45 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A directory under the system temp dir that is removed again when dropped.
+pub(crate) struct TempDir(PathBuf);
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let path = env::temp_dir().join(format!(
+            "repopt-{name}-{}-{}",
+            process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+impl TempDir {
+    /// A temp dir holding a new git repository, with a user to commit as.
+    pub(crate) fn new_repo(name: &str) -> Self {
+        let dir = TempDir::new(name);
+        gix::init(dir.path()).unwrap();
+        let config = dir.path().join(".git").join("config");
+        let mut contents = fs::read_to_string(&config).unwrap();
+        contents.push_str("[user]\n\tname = Test User\n\temail = test@example.com\n");
+        fs::write(config, contents).unwrap();
+        dir
+    }
+}
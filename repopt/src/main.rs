@@ -1,11 +1,17 @@
 mod actions;
 mod in_repo_db;
 mod output_formatter;
+mod tui;
 
 use std::path::PathBuf;
 
-use clap::Parser;
-use in_repo_db::structs::{ProjectId, TicketStatus, TicketType};
+use chrono::NaiveDate;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use in_repo_db::structs::{
+    GraphOutput, LinkType, MilestoneStatus, ProjectId, TicketPriority, TicketSeverity,
+    TicketSortOrder, TicketStatus, TicketType, WorkDuration,
+};
 
 type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
 type Result<T> = std::result::Result<T, Error>;
@@ -19,6 +25,7 @@ mod config {
     pub(crate) struct Config {
         pub formatter: OutputFormatter,
         pub irdb_path: PathBuf,
+        pub commit: bool,
     }
 
     pub(crate) static CONFIG: OnceLock<Config> = OnceLock::new();
@@ -27,16 +34,80 @@ mod config {
 #[derive(Parser, Debug)]
 #[command(name = "RepoRT", about = "CLI for RepoRT: In Repo Ticketing System")]
 struct Cli {
-    #[arg(long, default_value = "json", global = true)]
-    format: output_formatter::OutputFormatter,
+    #[arg(
+        long,
+        default_value = "json",
+        global = true,
+        value_parser = FormatArg::parser(&[FormatArg::Json, FormatArg::Yaml])
+    )]
+    format: FormatArg,
 
     #[arg(long, value_parser = clap::value_parser!(PathBuf), global = true, default_value = ".irdb")]
     irdb_path: PathBuf,
 
+    #[arg(
+        long,
+        global = true,
+        help = "Commit the files changed by this command (see also git.auto_commit)"
+    )]
+    commit: bool,
+
     #[command(subcommand)]
     base_command: BaseCommands,
 }
 
+/// Values of `--format`. Every command takes `json` and `yaml`, and a command with renderings
+/// of its own overrides the option to take those as well. They all share this one type because
+/// clap copies the value of a global option between a command and its subcommand.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Json,
+    Yaml,
+    /// Graphviz DOT.
+    Dot,
+    /// Mermaid flowchart.
+    Mermaid,
+    /// Comma separated values, one row per day.
+    Csv,
+    /// Plain text chart.
+    Chart,
+}
+
+impl FormatArg {
+    fn parser(formats: &'static [FormatArg]) -> impl TypedValueParser<Value = FormatArg> {
+        PossibleValuesParser::new(formats.iter().filter_map(ValueEnum::to_possible_value))
+            .map(|name| FormatArg::from_str(&name, false).expect("parsed from a possible value"))
+    }
+
+    fn formatter(self) -> output_formatter::OutputFormatter {
+        match self {
+            FormatArg::Yaml => output_formatter::OutputFormatter::Yaml,
+            // The command-specific formats are rendered by their own commands.
+            FormatArg::Json
+            | FormatArg::Dot
+            | FormatArg::Mermaid
+            | FormatArg::Csv
+            | FormatArg::Chart => output_formatter::OutputFormatter::Json,
+        }
+    }
+
+    fn graph_output(self) -> Option<GraphOutput> {
+        match self {
+            FormatArg::Dot => Some(GraphOutput::Dot),
+            FormatArg::Mermaid => Some(GraphOutput::Mermaid),
+            _ => None,
+        }
+    }
+
+    fn burndown_output(self) -> Option<actions::BurndownOutput> {
+        match self {
+            FormatArg::Csv => Some(actions::BurndownOutput::Csv),
+            FormatArg::Chart => Some(actions::BurndownOutput::Chart),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Parser, Debug)]
 enum BaseCommands {
     #[clap(name = "init", about = "Initialize a new repository")]
@@ -46,14 +117,349 @@ enum BaseCommands {
     Add(AddOptions),
     #[clap(name = "list", about = "List all tickets")]
     List(ListOptions),
-    #[clap(name = "show", about = "Show a ticket")]
-    Show { id: String },
-    #[clap(name = "edit", about = "Edit a ticket")]
-    Edit,
+    #[clap(
+        name = "show",
+        about = "Show a ticket, or `show milestone M0001` / `show project P0001`"
+    )]
+    Show {
+        #[clap(help = "Ticket ID, or the kind of item to show")]
+        target: String,
+        #[clap(help = "ID of the item when a kind is given")]
+        id: Option<String>,
+        #[clap(long, help = "Render the ticket and its subtasks as a tree")]
+        tree: bool,
+        #[clap(
+            long,
+            conflicts_with = "tree",
+            help = "Include the commits that reference the ticket"
+        )]
+        commits: bool,
+    },
+    #[clap(
+        name = "edit",
+        about = "Edit a ticket, or a milestone with `edit milestone M0001 --status closed`"
+    )]
+    Edit(EditTicketOptions),
     #[clap(name = "close", about = "Close a ticket")]
     Close,
     #[clap(name = "reopen", about = "Reopen a ticket")]
     Reopen,
+    #[clap(name = "assign", about = "Assign a ticket to a user (or \"me\")")]
+    Assign { id: String, user: String },
+    #[clap(name = "unassign", about = "Remove the assignee from a ticket")]
+    Unassign { id: String },
+    #[clap(name = "comment", about = "Comment on a ticket")]
+    Comment {
+        id: String,
+        #[clap(help = "Comment text; opens an editor when omitted")]
+        message: Option<String>,
+    },
+    #[clap(name = "start-timer", about = "Start timing work on a ticket")]
+    StartTimer { id: String },
+    #[clap(
+        name = "stop-timer",
+        about = "Stop the running timer and log the time on its ticket"
+    )]
+    StopTimer {
+        #[clap(help = "What the time was spent on")]
+        note: Option<String>,
+    },
+    #[clap(name = "log-time", about = "Log time spent on a ticket, e.g. 1h30m")]
+    LogTime {
+        id: String,
+        duration: WorkDuration,
+        #[clap(help = "What the time was spent on")]
+        note: Option<String>,
+        #[clap(long, help = "Day the work was done (defaults to today)")]
+        date: Option<NaiveDate>,
+    },
+    #[clap(
+        name = "timesheet",
+        about = "Summarise logged time per ticket and project"
+    )]
+    Timesheet {
+        #[clap(
+            long,
+            help = "Only time logged since this day: a weekday, today, yesterday or YYYY-MM-DD"
+        )]
+        since: Option<String>,
+        #[clap(long, help = "Only time logged by this user (use 'me' for yourself)")]
+        author: Option<String>,
+    },
+    #[clap(
+        name = "history",
+        about = "Field-level change log of a ticket from git"
+    )]
+    History {
+        id: String,
+        #[clap(
+            long,
+            num_args = 2,
+            value_names = ["REV1", "REV2"],
+            help = "Only compare the ticket between two revisions"
+        )]
+        diff: Option<Vec<String>>,
+    },
+    #[clap(
+        name = "blame",
+        about = "Show which commit last set each field of a ticket"
+    )]
+    Blame { id: String },
+    #[clap(
+        name = "commits",
+        about = "List the commits whose message references a ticket"
+    )]
+    Commits { id: String },
+    #[clap(
+        name = "process-commits",
+        about = "Close the tickets named by \"Fixes T0001\" or \"Closes T0001\" in new commits"
+    )]
+    ProcessCommits {
+        #[clap(
+            long,
+            help = "Only look at commits after this revision (default: since the last run; the first run only records HEAD)"
+        )]
+        since: Option<String>,
+    },
+    #[clap(
+        name = "estimate",
+        about = "Set a ticket's estimate in points or hours"
+    )]
+    Estimate {
+        id: String,
+        #[clap(required_unless_present = "clear")]
+        estimate: Option<u32>,
+        #[clap(long, conflicts_with = "estimate", help = "Remove the estimate")]
+        clear: bool,
+    },
+    #[clap(
+        name = "burndown",
+        about = "Remaining work per day from the ticket history"
+    )]
+    #[clap(group(clap::ArgGroup::new("scope").required(true).args(["milestone", "project"])))]
+    Burndown {
+        #[clap(long)]
+        milestone: Option<String>,
+        #[clap(long, value_parser = clap::value_parser!(ProjectId))]
+        project: Option<ProjectId>,
+        #[clap(long, value_parser = FormatArg::parser(&[
+            FormatArg::Json,
+            FormatArg::Yaml,
+            FormatArg::Csv,
+            FormatArg::Chart,
+        ]))]
+        format: Option<FormatArg>,
+    },
+    #[clap(
+        name = "link",
+        about = "Link two tickets, e.g. `link T0002 blocks T0005`"
+    )]
+    Link {
+        id: String,
+        #[clap(value_enum)]
+        link_type: LinkType,
+        target: String,
+    },
+    #[clap(name = "unlink", about = "Remove all links between two tickets")]
+    Unlink { id: String, target: String },
+    #[clap(
+        name = "check-links",
+        about = "Report dangling links and blocking cycles"
+    )]
+    CheckLinks,
+    #[clap(name = "graph", about = "Export tickets and their relationships")]
+    Graph {
+        #[clap(help = "Limit the graph to a project or to the tickets connected to a ticket")]
+        query: Option<String>,
+        #[clap(long, value_parser = FormatArg::parser(&[
+            FormatArg::Json,
+            FormatArg::Yaml,
+            FormatArg::Dot,
+            FormatArg::Mermaid,
+        ]))]
+        format: Option<FormatArg>,
+    },
+    #[clap(name = "board", about = "Print a Kanban board of tickets by status")]
+    Board {
+        #[clap(long, value_parser = clap::value_parser!(ProjectId))]
+        project: Option<ProjectId>,
+    },
+    #[clap(name = "sprint", about = "Plan and review sprints")]
+    #[command(subcommand)]
+    Sprint(SprintOptions),
+    #[clap(name = "label", about = "Manage ticket labels")]
+    #[command(subcommand)]
+    Label(LabelOptions),
+    #[clap(
+        name = "tui",
+        about = "Browse and triage tickets in a full-screen Kanban board"
+    )]
+    Tui,
+    #[clap(
+        name = "start",
+        about = "Create and check out a branch for a ticket, and move it to In Progress"
+    )]
+    Start { id: String },
+    #[clap(
+        name = "current",
+        about = "Show the ticket the current branch belongs to"
+    )]
+    Current,
+    #[clap(
+        name = "renumber",
+        about = "Give new IDs to tickets whose ID collides with another, e.g. after a merge"
+    )]
+    Renumber,
+    #[clap(
+        name = "doctor",
+        about = "Check every file of the database and report all problems found"
+    )]
+    Doctor {
+        #[clap(
+            long,
+            help = "Rename misnamed files and correct values that only differ in case or spacing"
+        )]
+        fix: bool,
+    },
+    #[clap(
+        name = "merge-driver",
+        about = "Merge two versions of a ticket field by field (git merge driver)"
+    )]
+    MergeDriver {
+        #[clap(help = "The common ancestor (%O)")]
+        base: PathBuf,
+        #[clap(help = "Our version (%A), which receives the result")]
+        ours: PathBuf,
+        #[clap(help = "Their version (%B)")]
+        theirs: PathBuf,
+    },
+    #[clap(name = "hooks", about = "Manage the git hooks repopt provides")]
+    #[command(subcommand)]
+    Hooks(HooksOptions),
+    #[clap(
+        name = "hook",
+        about = "Run a git hook (called by the installed hook scripts)"
+    )]
+    #[command(subcommand)]
+    Hook(HookOptions),
+}
+
+#[derive(Parser, Debug)]
+enum HooksOptions {
+    #[clap(
+        name = "install",
+        about = "Install the commit-msg, prepare-commit-msg and post-checkout hooks"
+    )]
+    Install {
+        #[clap(long, help = "Replace hooks that were not installed by repopt")]
+        force: bool,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum HookOptions {
+    #[clap(
+        name = "commit-msg",
+        about = "Reject commit messages that reference unknown tickets"
+    )]
+    CommitMsg { message_file: String },
+    #[clap(
+        name = "prepare-commit-msg",
+        about = "Reference the ticket the branch is named after"
+    )]
+    PrepareCommitMsg {
+        message_file: String,
+        source: Option<String>,
+        #[clap(value_name = "COMMIT")]
+        sha: Option<String>,
+    },
+    #[clap(
+        name = "post-checkout",
+        about = "Offer to move the branch's ticket to In Progress"
+    )]
+    PostCheckout {
+        previous_head: String,
+        new_head: String,
+        #[clap(help = "1 for a branch checkout, 0 for a file checkout")]
+        branch_checkout: u8,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum SprintOptions {
+    #[clap(
+        name = "new",
+        about = "Create a sprint (two weeks after the last one by default)"
+    )]
+    New {
+        #[clap(long)]
+        name: Option<String>,
+        #[clap(long, value_parser = clap::value_parser!(NaiveDate), help = "Start date (YYYY-MM-DD)")]
+        start: Option<NaiveDate>,
+        #[clap(long, value_parser = clap::value_parser!(NaiveDate), help = "End date (YYYY-MM-DD)")]
+        end: Option<NaiveDate>,
+        #[clap(long, help = "Maximum number of tickets to commit to")]
+        capacity: Option<u32>,
+    },
+    #[clap(
+        name = "add",
+        about = "Add tickets to a sprint (the current one by default)"
+    )]
+    Add {
+        #[clap(required = true)]
+        tickets: Vec<String>,
+        #[clap(long)]
+        sprint: Option<String>,
+    },
+    #[clap(name = "rm", alias = "remove", about = "Remove tickets from a sprint")]
+    Rm {
+        #[clap(required = true)]
+        tickets: Vec<String>,
+        #[clap(long)]
+        sprint: Option<String>,
+    },
+    #[clap(name = "list", about = "List all sprints")]
+    List,
+    #[clap(name = "current", about = "Summarise the sprint running today")]
+    Current,
+    #[clap(name = "summary", about = "Summarise committed vs completed tickets")]
+    Summary { sprint: Option<String> },
+    #[clap(
+        name = "carry-over",
+        about = "Carry unfinished tickets into the following sprint"
+    )]
+    CarryOver {
+        #[clap(long, help = "Sprint to carry over from (the current one by default)")]
+        from: Option<String>,
+    },
+}
+
+#[derive(Parser, Debug)]
+enum LabelOptions {
+    #[clap(name = "add", about = "Add labels to a ticket")]
+    Add {
+        id: String,
+        #[clap(required = true)]
+        labels: Vec<String>,
+    },
+    #[clap(name = "rm", alias = "remove", about = "Remove labels from a ticket")]
+    Rm {
+        id: String,
+        #[clap(required = true)]
+        labels: Vec<String>,
+    },
+    #[clap(
+        name = "list",
+        about = "List all labels in use with their ticket counts"
+    )]
+    List,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ShowKind {
+    Ticket,
+    Project,
+    Milestone,
 }
 
 #[derive(Parser, Debug)]
@@ -62,6 +468,18 @@ enum AddOptions {
     Ticket(AddTicketOptions),
     #[clap(name = "project", about = "Add a new project")]
     Project(AddProjectOptions),
+    #[clap(name = "milestone", about = "Add a new milestone")]
+    Milestone(AddMilestoneOptions),
+}
+
+#[derive(Parser, Debug)]
+struct AddMilestoneOptions {
+    #[clap(long)]
+    name: Option<String>,
+    #[clap(long)]
+    description: Option<String>,
+    #[clap(long, value_parser = clap::value_parser!(NaiveDate), help = "Due date (YYYY-MM-DD)")]
+    due: Option<NaiveDate>,
 }
 
 #[derive(Parser, Debug)]
@@ -70,6 +488,11 @@ struct AddProjectOptions {
     name: Option<String>,
     #[clap(long, value_enum)]
     description: Option<String>,
+    #[clap(
+        long,
+        help = "Prefix for the project's ticket IDs, e.g. API for API-1, API-2, ..."
+    )]
+    key: Option<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -82,6 +505,54 @@ struct AddTicketOptions {
     status: Option<TicketStatus>,
     #[clap(long, value_enum)]
     ticket_type: Option<TicketType>,
+    #[clap(long, value_enum)]
+    priority: Option<TicketPriority>,
+    #[clap(long, value_enum, help = "Bug severity (bugs only)")]
+    severity: Option<TicketSeverity>,
+    #[clap(long, help = "Parent ticket, e.g. the epic this is a subtask of")]
+    parent: Option<String>,
+}
+
+#[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+struct EditTicketOptions {
+    #[command(subcommand)]
+    item: Option<EditItem>,
+    id: Option<String>,
+    #[clap(long)]
+    title: Option<String>,
+    #[clap(long)]
+    description: Option<String>,
+    #[clap(long, value_enum)]
+    status: Option<TicketStatus>,
+    #[clap(long, value_enum)]
+    ticket_type: Option<TicketType>,
+    #[clap(long, value_enum)]
+    priority: Option<TicketPriority>,
+    #[clap(long, value_enum, help = "Bug severity (bugs only)")]
+    severity: Option<TicketSeverity>,
+    #[clap(long, help = "Parent ticket, e.g. the epic this is a subtask of")]
+    parent: Option<String>,
+    #[clap(long, conflicts_with = "parent", help = "Remove the parent ticket")]
+    clear_parent: bool,
+    #[clap(long, help = "Milestone the ticket is planned for")]
+    milestone: Option<String>,
+    #[clap(
+        long,
+        conflicts_with = "milestone",
+        help = "Remove the ticket from its milestone"
+    )]
+    clear_milestone: bool,
+}
+
+#[derive(Parser, Debug)]
+enum EditItem {
+    #[clap(name = "milestone", about = "Close or reopen a milestone")]
+    Milestone {
+        id: String,
+        #[clap(long, value_enum)]
+        status: MilestoneStatus,
+    },
 }
 
 #[derive(Parser, Debug)]
@@ -92,14 +563,38 @@ struct ListOptions {
     ticket_type: Option<TicketType>,
     #[clap(long, value_parser = clap::value_parser!(ProjectId))]
     project: Option<ProjectId>,
+    #[clap(long, help = "Only tickets assigned to this user (or \"me\")")]
+    assignee: Option<String>,
+    #[clap(
+        long = "label",
+        help = "Only tickets with this label (repeat to require several)"
+    )]
+    labels: Vec<String>,
+    #[clap(long, help = "Only tickets with an open blocker")]
+    blocked: bool,
+    #[clap(
+        long,
+        conflicts_with = "blocked",
+        help = "Only tickets without an open blocker"
+    )]
+    unblocked: bool,
+    #[clap(long, help = "Only direct subtasks of this ticket")]
+    children_of: Option<String>,
+    #[clap(long, help = "Only tickets planned for this milestone")]
+    milestone: Option<String>,
+    #[clap(long, value_enum, default_value_t)]
+    sort: TicketSortOrder,
 }
 
+#[allow(clippy::too_many_lines)]
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
 
     let _ = config::CONFIG.set(config::Config {
-        formatter: cli.format,
+        formatter: cli.format.formatter(),
         irdb_path: cli.irdb_path,
+        commit: cli.commit,
     });
 
     match cli.base_command {
@@ -109,18 +604,176 @@ fn main() -> Result<()> {
             ticket_options.description,
             ticket_options.status,
             ticket_options.ticket_type,
+            ticket_options.priority,
+            ticket_options.severity,
+            ticket_options.parent,
+        ),
+        BaseCommands::Add(AddOptions::Project(project_options)) => actions::add_new_project(
+            project_options.name,
+            project_options.description,
+            project_options.key,
+        ),
+        BaseCommands::Add(AddOptions::Milestone(milestone_options)) => actions::add_new_milestone(
+            milestone_options.name,
+            milestone_options.description,
+            milestone_options.due,
+        ),
+        BaseCommands::List(options) => actions::list_all_tickets(
+            actions::ListFilters {
+                status: options.status,
+                ticket_type: options.ticket_type,
+                project: options.project,
+                assignee: options.assignee,
+                labels: options.labels,
+                blocked: options.blocked,
+                unblocked: options.unblocked,
+                children_of: options.children_of,
+                milestone: options.milestone,
+            },
+            options.sort,
+        ),
+        BaseCommands::Show {
+            target,
+            id: None,
+            tree,
+            commits,
+        } => actions::show_ticket(target, tree, commits),
+        BaseCommands::Show {
+            target,
+            id: Some(id),
+            tree,
+            commits,
+        } => match ShowKind::from_str(&target, true)? {
+            ShowKind::Ticket => actions::show_ticket(id, tree, commits),
+            ShowKind::Project => actions::show_project(id),
+            ShowKind::Milestone => actions::show_milestone(id),
+        },
+        BaseCommands::Edit(EditTicketOptions {
+            item: Some(EditItem::Milestone { id, status }),
+            ..
+        }) => actions::edit_milestone(id, status),
+        BaseCommands::Edit(options) => actions::edit_ticket(
+            options.id,
+            actions::TicketEdits {
+                title: options.title,
+                description: options.description,
+                status: options.status,
+                ticket_type: options.ticket_type,
+                priority: options.priority,
+                severity: options.severity,
+                parent: options.parent,
+                clear_parent: options.clear_parent,
+                milestone: options.milestone,
+                clear_milestone: options.clear_milestone,
+            },
         ),
-        BaseCommands::Add(AddOptions::Project(project_options)) => {
-            actions::add_new_project(project_options.name, project_options.description)
-        }
-        BaseCommands::List(options) => {
-            actions::list_all_tickets(options.status, options.ticket_type, options.project)
-        }
-        BaseCommands::Show { id } => actions::show_ticket(id),
-        BaseCommands::Edit => unimplemented!(),
         BaseCommands::Close => actions::close_ticket(),
         BaseCommands::Reopen => actions::reopen_ticket(),
+        BaseCommands::Assign { id, user } => actions::assign_ticket(id, &user),
+        BaseCommands::Unassign { id } => actions::unassign_ticket(id),
+        BaseCommands::Comment { id, message } => actions::comment_on_ticket(id, message),
+        BaseCommands::StartTimer { id } => actions::start_timer(id),
+        BaseCommands::StopTimer { note } => actions::stop_timer(note),
+        BaseCommands::LogTime {
+            id,
+            duration,
+            note,
+            date,
+        } => actions::log_time(id, duration, note, date),
+        BaseCommands::Timesheet { since, author } => {
+            actions::timesheet(since.as_deref(), author.as_deref())
+        }
+        BaseCommands::History { id, diff } => actions::show_history(id, diff.as_deref()),
+        BaseCommands::Blame { id } => actions::blame_ticket(id),
+        BaseCommands::Commits { id } => actions::list_commits(id),
+        BaseCommands::ProcessCommits { since } => actions::process_commits(since.as_deref()),
+        BaseCommands::Estimate { id, estimate, .. } => actions::estimate_ticket(id, estimate),
+        BaseCommands::Burndown {
+            milestone,
+            project,
+            format,
+        } => actions::burndown(
+            milestone.map(Into::into).as_ref(),
+            project.as_ref(),
+            format.and_then(FormatArg::burndown_output),
+        ),
+        BaseCommands::Link {
+            id,
+            link_type,
+            target,
+        } => actions::link_tickets(id, link_type, target),
+        BaseCommands::Unlink { id, target } => actions::unlink_tickets(id, target),
+        BaseCommands::CheckLinks => actions::check_links(),
+        BaseCommands::Graph { query, format } => {
+            actions::export_graph(query.as_deref(), format.and_then(FormatArg::graph_output))
+        }
+        BaseCommands::Board { project } => actions::print_board(project.as_ref()),
+        BaseCommands::Sprint(options) => run_sprint_command(options),
+        BaseCommands::Start { id } => actions::start_ticket(id),
+        BaseCommands::Current => actions::current_ticket(),
+        BaseCommands::Renumber => actions::renumber_tickets(),
+        BaseCommands::Doctor { fix } => actions::run_doctor(fix),
+        BaseCommands::MergeDriver { base, ours, theirs } => {
+            actions::merge_driver(&base, &ours, &theirs)
+        }
+        BaseCommands::Hooks(HooksOptions::Install { force }) => actions::install_hooks(force),
+        BaseCommands::Hook(options) => run_hook_command(options),
+        BaseCommands::Label(options) => run_label_command(options),
+        BaseCommands::Tui => tui::run(),
     }?;
 
+    if let Some((commit, message)) = in_repo_db::commit_written_files(&command_name(&matches))? {
+        println!("[{commit}] {message}");
+    }
     Ok(())
 }
+
+/// The full subcommand path, e.g. `sprint add`, as used in automatic commit messages.
+fn command_name(matches: &ArgMatches) -> String {
+    let mut names = Vec::new();
+    let mut current = matches;
+    while let Some((name, sub_matches)) = current.subcommand() {
+        names.push(name);
+        current = sub_matches;
+    }
+    names.join(" ")
+}
+
+fn run_sprint_command(options: SprintOptions) -> Result<()> {
+    match options {
+        SprintOptions::New {
+            name,
+            start,
+            end,
+            capacity,
+        } => actions::new_sprint(name, start, end, capacity),
+        SprintOptions::Add { tickets, sprint } => actions::add_to_sprint(tickets, sprint),
+        SprintOptions::Rm { tickets, sprint } => actions::remove_from_sprint(tickets, sprint),
+        SprintOptions::List => actions::list_sprints(),
+        SprintOptions::Current => actions::sprint_summary(None),
+        SprintOptions::Summary { sprint } => actions::sprint_summary(sprint),
+        SprintOptions::CarryOver { from } => actions::carry_over(from),
+    }
+}
+
+fn run_hook_command(options: HookOptions) -> Result<()> {
+    match options {
+        HookOptions::CommitMsg { message_file } => actions::commit_msg_hook(&message_file),
+        HookOptions::PrepareCommitMsg {
+            message_file,
+            source,
+            ..
+        } => actions::prepare_commit_msg_hook(&message_file, source.as_deref()),
+        HookOptions::PostCheckout {
+            branch_checkout, ..
+        } => actions::post_checkout_hook(branch_checkout == 1),
+    }
+}
+
+fn run_label_command(options: LabelOptions) -> Result<()> {
+    match options {
+        LabelOptions::Add { id, labels } => actions::add_labels(id, labels),
+        LabelOptions::Rm { id, labels } => actions::remove_labels(id, labels),
+        LabelOptions::List => actions::list_labels(),
+    }
+}
@@ -0,0 +1,440 @@
+use crate::in_repo_db;
+use crate::in_repo_db::structs::{
+    Comment, InRepoDB, Ticket, TicketFilters, TicketPriority, TicketSortOrder, TicketStatus,
+};
+use clap::ValueEnum;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum View {
+    Board,
+    List,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Mode {
+    Normal,
+    Filter,
+    EditTitle,
+    Comment,
+    Help,
+}
+
+pub(super) struct App {
+    pub db: InRepoDB,
+    pub view: View,
+    pub mode: Mode,
+    /// Index into `TicketStatus::value_variants()` of the focused board column.
+    pub column: usize,
+    /// Selected row per board column, and for the list view in the last slot.
+    pub rows: Vec<usize>,
+    pub filter: String,
+    pub input: String,
+    pub message: Option<String>,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub(super) fn new(db: InRepoDB) -> Self {
+        App {
+            db,
+            view: View::Board,
+            mode: Mode::Normal,
+            column: 0,
+            rows: vec![0; TicketStatus::value_variants().len() + 1],
+            filter: String::new(),
+            input: String::new(),
+            message: None,
+            should_quit: false,
+        }
+    }
+
+    pub(super) fn statuses() -> &'static [TicketStatus] {
+        TicketStatus::value_variants()
+    }
+
+    pub(super) fn reload(&mut self) -> Result<()> {
+        self.db = in_repo_db::collect_in_repo_db()?;
+        self.clamp_rows();
+        Ok(())
+    }
+
+    /// Reloads after a change outside the TUI. A file caught half-written fails to load, so
+    /// the error is shown and the last good state stays on screen until the next change.
+    pub(super) fn refresh(&mut self) {
+        if let Err(e) = self.reload() {
+            self.message = Some(format!("Error: {e}"));
+        }
+    }
+
+    fn matches_filter(&self, ticket: &Ticket) -> bool {
+        if self.filter.is_empty() {
+            return true;
+        }
+        let filter = self.filter.to_lowercase();
+        ticket.id().to_string().to_lowercase().contains(&filter)
+            || ticket.title().to_string().to_lowercase().contains(&filter)
+            || ticket
+                .labels()
+                .iter()
+                .any(|label| label.to_string().to_lowercase().contains(&filter))
+    }
+
+    pub(super) fn column_tickets(&self, column: usize) -> Vec<&Ticket> {
+        let mut tickets: Vec<&Ticket> = self
+            .db
+            .iter_tickets()
+            .with_status(Self::statuses()[column].clone())
+            .filter(|ticket| self.matches_filter(ticket))
+            .collect();
+        tickets.sort_by(|a, b| TicketSortOrder::Priority.compare(a, b));
+        tickets
+    }
+
+    pub(super) fn list_tickets(&self) -> Vec<&Ticket> {
+        let mut tickets: Vec<&Ticket> = self
+            .db
+            .iter_tickets()
+            .filter(|ticket| self.matches_filter(ticket))
+            .collect();
+        tickets.sort_by(|a, b| TicketSortOrder::Priority.compare(a, b));
+        tickets
+    }
+
+    fn visible_tickets(&self) -> Vec<&Ticket> {
+        match self.view {
+            View::Board => self.column_tickets(self.column),
+            View::List => self.list_tickets(),
+        }
+    }
+
+    fn row_slot(&self) -> usize {
+        match self.view {
+            View::Board => self.column,
+            View::List => self.rows.len() - 1,
+        }
+    }
+
+    pub(super) fn selected_row(&self) -> usize {
+        self.rows[self.row_slot()]
+    }
+
+    pub(super) fn selected_ticket(&self) -> Option<&Ticket> {
+        self.visible_tickets().get(self.selected_row()).copied()
+    }
+
+    fn clamp_rows(&mut self) {
+        for column in 0..Self::statuses().len() {
+            let len = self.column_tickets(column).len();
+            self.rows[column] = self.rows[column].min(len.saturating_sub(1));
+        }
+        let len = self.list_tickets().len();
+        let slot = self.rows.len() - 1;
+        self.rows[slot] = self.rows[slot].min(len.saturating_sub(1));
+    }
+
+    fn move_row(&mut self, delta: isize) {
+        let len = self.visible_tickets().len();
+        let slot = self.row_slot();
+        self.rows[slot] = self.rows[slot]
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    fn move_column(&mut self, delta: isize) {
+        self.column = self
+            .column
+            .saturating_add_signed(delta)
+            .min(Self::statuses().len() - 1);
+    }
+
+    fn write_selected(&mut self, change: impl FnOnce(&mut Ticket)) -> Result<Option<Ticket>> {
+        let Some(mut ticket) = self.selected_ticket().cloned() else {
+            return Ok(None);
+        };
+        change(&mut ticket);
+        in_repo_db::verify_and_write(&ticket)?;
+        self.reload()?;
+        Ok(Some(ticket))
+    }
+
+    /// Moves the selected ticket into the neighbouring status column and keeps it selected.
+    pub(super) fn move_ticket(&mut self, delta: isize) -> Result<()> {
+        let Some(current) = self.selected_ticket() else {
+            return Ok(());
+        };
+        let statuses = Self::statuses();
+        let from = statuses
+            .iter()
+            .position(|status| status == current.status())
+            .unwrap_or_default();
+        let to = from.saturating_add_signed(delta).min(statuses.len() - 1);
+        if from == to {
+            return Ok(());
+        }
+        let status = statuses[to].clone();
+        if let Some(ticket) = self.write_selected(|ticket| move_to(ticket, status))? {
+            self.message = Some(format!("{} moved to {}", ticket.id(), ticket.status()));
+            self.select(&ticket);
+        }
+        Ok(())
+    }
+
+    fn select(&mut self, ticket: &Ticket) {
+        if self.view == View::Board {
+            self.column = Self::statuses()
+                .iter()
+                .position(|status| status == ticket.status())
+                .unwrap_or(self.column);
+        }
+        let row = self
+            .visible_tickets()
+            .iter()
+            .position(|candidate| candidate.id() == ticket.id());
+        if let Some(row) = row {
+            let slot = self.row_slot();
+            self.rows[slot] = row;
+        }
+    }
+
+    fn cycle_priority(&mut self) -> Result<()> {
+        let next = |priority: Option<TicketPriority>| {
+            let priorities = TicketPriority::value_variants();
+            priority
+                .and_then(|p| priorities.iter().position(|c| *c == p))
+                .map_or(priorities[0], |i| priorities[(i + 1) % priorities.len()])
+        };
+        if let Some(ticket) =
+            self.write_selected(|ticket| ticket.set_priority(next(ticket.priority())))?
+        {
+            self.message = Some(format!(
+                "{} priority set to {}",
+                ticket.id(),
+                ticket.priority().map(|p| p.to_string()).unwrap_or_default()
+            ));
+            self.select(&ticket);
+        }
+        Ok(())
+    }
+
+    fn submit_input(&mut self) -> Result<()> {
+        let input = std::mem::take(&mut self.input);
+        let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+        let input = input.trim().to_owned();
+        if input.is_empty() {
+            return Ok(());
+        }
+        match mode {
+            Mode::EditTitle => {
+                if let Some(ticket) =
+                    self.write_selected(|ticket| ticket.set_title(input.into()))?
+                {
+                    self.message = Some(format!("{} renamed", ticket.id()));
+                    self.select(&ticket);
+                }
+            }
+            Mode::Comment => {
+                if let Some(ticket) = self.selected_ticket() {
+                    let comment = Comment::new(
+                        ticket.id().clone(),
+                        in_repo_db::current_user().ok(),
+                        input.into(),
+                    );
+                    in_repo_db::verify_and_write(&comment)?;
+                    self.message = Some(format!("Commented on {}", comment.ticket()));
+                    self.reload()?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    pub(super) fn handle_key(&mut self, key: KeyEvent) -> Result<()> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.should_quit = true;
+            return Ok(());
+        }
+        match self.mode {
+            Mode::Normal => self.handle_normal_key(key.code)?,
+            Mode::Help => self.mode = Mode::Normal,
+            Mode::Filter => match key.code {
+                KeyCode::Enter => self.mode = Mode::Normal,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.mode = Mode::Normal;
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                }
+                KeyCode::Char(c) => self.filter.push(c),
+                _ => {}
+            },
+            Mode::EditTitle | Mode::Comment => match key.code {
+                KeyCode::Enter => self.submit_input()?,
+                KeyCode::Esc => {
+                    self.input.clear();
+                    self.mode = Mode::Normal;
+                }
+                KeyCode::Backspace => {
+                    self.input.pop();
+                }
+                KeyCode::Char(c) => self.input.push(c),
+                _ => {}
+            },
+        }
+        self.clamp_rows();
+        Ok(())
+    }
+
+    fn handle_normal_key(&mut self, code: KeyCode) -> Result<()> {
+        self.message = None;
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => self.should_quit = true,
+            KeyCode::Char('?') => self.mode = Mode::Help,
+            KeyCode::Tab => {
+                self.view = match self.view {
+                    View::Board => View::List,
+                    View::List => View::Board,
+                }
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.move_column(-1),
+            KeyCode::Right | KeyCode::Char('l') => self.move_column(1),
+            KeyCode::Up | KeyCode::Char('k') => self.move_row(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_row(1),
+            KeyCode::Char('H' | '<') => self.move_ticket(-1)?,
+            KeyCode::Char('L' | '>') => self.move_ticket(1)?,
+            KeyCode::Char('x') => {
+                if let Some(ticket) = self.write_selected(|ticket| ticket.close(None))? {
+                    self.message = Some(format!("{} closed", ticket.id()));
+                    self.select(&ticket);
+                }
+            }
+            KeyCode::Char('p') => self.cycle_priority()?,
+            KeyCode::Char('e') => {
+                if let Some(ticket) = self.selected_ticket() {
+                    self.input = ticket.title().to_string();
+                    self.mode = Mode::EditTitle;
+                }
+            }
+            KeyCode::Char('c') if self.selected_ticket().is_some() => self.mode = Mode::Comment,
+            KeyCode::Char('/') => self.mode = Mode::Filter,
+            KeyCode::Char('r') => {
+                self.reload()?;
+                self.message = Some("Reloaded".to_owned());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// Changes the status like the `close` and `reopen` commands do when the move enters or
+/// leaves the Closed column, so a resolution never outlives the ticket being closed.
+fn move_to(ticket: &mut Ticket, status: TicketStatus) {
+    if status == TicketStatus::Closed {
+        ticket.close(None);
+    } else if *ticket.status() == TicketStatus::Closed {
+        ticket.reopen(Some(status));
+    } else {
+        ticket.set_status(status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::in_repo_db::structs::tests::ticket_fixture;
+    use std::collections::BTreeMap;
+
+    fn app() -> App {
+        let tickets = [
+            ("T0001", "Fix login", TicketStatus::Backlog),
+            ("T0002", "Add search", TicketStatus::Backlog),
+            ("T0003", "Docs", TicketStatus::Closed),
+        ]
+        .into_iter()
+        .map(|(id, title, status)| {
+            let ticket = ticket_fixture(id, title, status);
+            (ticket.id().clone(), ticket)
+        })
+        .collect();
+        let db = InRepoDB::new(
+            BTreeMap::new(),
+            BTreeMap::new(),
+            BTreeMap::new(),
+            tickets,
+            BTreeMap::new(),
+        );
+        App::new(db)
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_board_navigation() {
+        let mut app = app();
+        assert_eq!(app.column_tickets(0).len(), 2);
+        assert_eq!(app.column_tickets(2).len(), 1);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_ticket().unwrap().id().to_string(), "T0002");
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_row(), 1);
+        press(&mut app, KeyCode::Right);
+        assert!(app.selected_ticket().is_none());
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.selected_ticket().unwrap().id().to_string(), "T0003");
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.column, 2);
+    }
+
+    #[test]
+    fn test_filter_and_list_view() {
+        let mut app = app();
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.view, View::List);
+        assert_eq!(app.list_tickets().len(), 3);
+        press(&mut app, KeyCode::Char('/'));
+        for c in "search".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.mode, Mode::Normal);
+        assert_eq!(app.list_tickets().len(), 1);
+        assert_eq!(app.selected_ticket().unwrap().id().to_string(), "T0002");
+        press(&mut app, KeyCode::Char('/'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.list_tickets().len(), 3);
+    }
+
+    #[test]
+    fn test_failed_refresh_keeps_tickets() {
+        let mut app = app();
+        // Tests have no configuration to load the database with, so reloading fails.
+        app.refresh();
+        assert!(app.message.as_ref().unwrap().starts_with("Error:"));
+        assert!(!app.should_quit);
+        assert_eq!(app.column_tickets(0).len(), 2);
+    }
+
+    #[test]
+    fn test_moving_out_of_closed_drops_the_resolution() {
+        let mut ticket = ticket_fixture("T0001", "Fix login", TicketStatus::Backlog);
+        ticket.close(Some("Duplicate".into()));
+        move_to(&mut ticket, TicketStatus::InProgress);
+        assert_eq!(*ticket.status(), TicketStatus::InProgress);
+        assert_eq!(ticket.resolution(), None);
+
+        move_to(&mut ticket, TicketStatus::Backlog);
+        assert_eq!(*ticket.status(), TicketStatus::Backlog);
+        move_to(&mut ticket, TicketStatus::Closed);
+        assert_eq!(*ticket.status(), TicketStatus::Closed);
+        assert_eq!(ticket.resolution(), None);
+    }
+}
@@ -0,0 +1,47 @@
+mod app;
+mod ui;
+
+use crate::in_repo_db;
+use app::App;
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+use ratatui::DefaultTerminal;
+use std::time::Duration;
+
+type Error = Box<dyn std::error::Error>; // replace this with set error types for production code.
+type Result<T> = std::result::Result<T, Error>;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub(crate) fn run() -> Result<()> {
+    let app = App::new(in_repo_db::collect_in_repo_db()?);
+    let mut terminal = ratatui::init();
+    let result = event_loop(&mut terminal, app);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, mut app: App) -> Result<()> {
+    let mut stamp = in_repo_db::modification_stamp()?;
+    while !app.should_quit {
+        terminal.draw(|frame| ui::draw(frame, &app))?;
+        if event::poll(POLL_INTERVAL)? {
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    if let Err(e) = app.handle_key(key) {
+                        app.message = Some(format!("Error: {e}"));
+                    }
+                }
+            }
+        }
+        // Pick up edits made outside the TUI, e.g. by a `git pull` or another repopt command.
+        match in_repo_db::modification_stamp() {
+            Ok(current) if current != stamp => {
+                stamp = current;
+                app.refresh();
+            }
+            Ok(_) => {}
+            Err(e) => app.message = Some(format!("Error: {e}")),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use super::app::{App, Mode, View};
+use crate::in_repo_db::structs::{Ticket, TicketStatus};
+use ratatui::layout::{Constraint, Flex, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+
+const HELP: &[(&str, &str)] = &[
+    ("Tab", "switch between board and list"),
+    ("←/→ h/l", "focus column"),
+    ("↑/↓ k/j", "select ticket"),
+    ("H/< L/>", "move ticket to previous/next status"),
+    ("x", "close ticket"),
+    ("e", "edit title"),
+    ("p", "cycle priority"),
+    ("c", "comment"),
+    ("/", "filter by ID, title or label"),
+    ("r", "reload from disk"),
+    ("q/Esc", "quit"),
+];
+
+pub(super) fn draw(frame: &mut Frame, app: &App) {
+    let [main, status_bar] =
+        Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+    let [tickets, detail] =
+        Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(main);
+    match app.view {
+        View::Board => draw_board(frame, app, tickets),
+        View::List => draw_list(frame, app, tickets),
+    }
+    draw_detail(frame, app, detail);
+    draw_status_bar(frame, app, status_bar);
+    if app.mode == Mode::Help {
+        draw_help(frame);
+    }
+}
+
+fn status_colour(status: &TicketStatus) -> Color {
+    match status {
+        TicketStatus::Backlog => Color::Gray,
+        TicketStatus::InProgress => Color::Yellow,
+        TicketStatus::Closed => Color::Green,
+    }
+}
+
+fn ticket_line(ticket: &Ticket, show_status: bool) -> Line<'static> {
+    let mut spans = vec![Span::styled(ticket.id().to_string(), Style::new().bold())];
+    if let Some(priority) = ticket.priority() {
+        spans.push(Span::styled(
+            format!(" {priority}"),
+            Style::new().fg(Color::Red),
+        ));
+    }
+    if show_status {
+        spans.push(Span::styled(
+            format!(" [{}]", ticket.status()),
+            Style::new().fg(status_colour(ticket.status())),
+        ));
+    }
+    spans.push(Span::raw(format!(" {}", ticket.title())));
+    Line::from(spans)
+}
+
+fn highlight(active: bool) -> Style {
+    if active {
+        Style::new().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::new()
+    }
+}
+
+fn draw_board(frame: &mut Frame, app: &App, area: Rect) {
+    let statuses = App::statuses();
+    let columns = Layout::horizontal(vec![Constraint::Fill(1); statuses.len()]).split(area);
+    for (index, (status, column)) in statuses.iter().zip(columns.iter()).enumerate() {
+        let tickets = app.column_tickets(index);
+        let focused = index == app.column;
+        let items: Vec<ListItem> = tickets
+            .iter()
+            .map(|ticket| ListItem::new(ticket_line(ticket, false)))
+            .collect();
+        let border_style = if focused {
+            Style::new().fg(status_colour(status))
+        } else {
+            Style::new().fg(Color::DarkGray)
+        };
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(border_style)
+                    .title(format!(" {status} ({}) ", tickets.len())),
+            )
+            .highlight_style(highlight(focused));
+        let mut state = ListState::default().with_selected(Some(app.rows[index]));
+        frame.render_stateful_widget(list, *column, &mut state);
+    }
+}
+
+fn draw_list(frame: &mut Frame, app: &App, area: Rect) {
+    let tickets = app.list_tickets();
+    let items: Vec<ListItem> = tickets
+        .iter()
+        .map(|ticket| ListItem::new(ticket_line(ticket, true)))
+        .collect();
+    let title = if app.filter.is_empty() {
+        format!(" Tickets ({}) ", tickets.len())
+    } else {
+        format!(" Tickets matching \"{}\" ({}) ", app.filter, tickets.len())
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(highlight(true));
+    let mut state = ListState::default().with_selected(Some(app.selected_row()));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn field(name: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{name}: "), Style::new().bold()),
+        Span::raw(value),
+    ])
+}
+
+fn draw_detail(frame: &mut Frame, app: &App, area: Rect) {
+    let block = Block::default().borders(Borders::ALL).title(" Details ");
+    let Some(ticket) = app.selected_ticket() else {
+        frame.render_widget(Paragraph::new("No ticket selected").block(block), area);
+        return;
+    };
+    let optional = |value: Option<String>| value.unwrap_or_else(|| "-".to_owned());
+    let mut lines = vec![
+        Line::styled(
+            format!("{} {}", ticket.id(), ticket.title()),
+            Style::new().bold(),
+        ),
+        Line::default(),
+        field("Status", ticket.status().to_string()),
+        field("Type", ticket.ticket_type().to_string()),
+        field(
+            "Priority",
+            optional(ticket.priority().map(|p| p.to_string())),
+        ),
+        field("Project", ticket.get_project_id().to_string()),
+        field(
+            "Assignee",
+            optional(ticket.assignee().map(ToString::to_string)),
+        ),
+    ];
+    if !ticket.labels().is_empty() {
+        let labels: Vec<String> = ticket.labels().iter().map(ToString::to_string).collect();
+        lines.push(field("Labels", labels.join(", ")));
+    }
+    if let Some(parent) = ticket.parent() {
+        lines.push(field("Parent", parent.to_string()));
+    }
+    for link in app.db.get_links(ticket.id()) {
+        lines.push(field("Link", link.to_string()));
+    }
+    lines.push(Line::default());
+    lines.extend(
+        ticket
+            .description()
+            .to_string()
+            .lines()
+            .map(|line| Line::raw(line.to_owned())),
+    );
+    let comments = app.db.get_comments(ticket.id());
+    if !comments.is_empty() {
+        lines.push(Line::default());
+        lines.push(Line::styled(
+            format!("Comments ({})", comments.len()),
+            Style::new().bold(),
+        ));
+        for comment in comments {
+            let author = optional(comment.author().map(|a| a.name().to_owned()));
+            lines.push(Line::styled(
+                format!("{author} · {}", comment.created().format("%Y-%m-%d %H:%M")),
+                Style::new().fg(Color::DarkGray),
+            ));
+            lines.push(Line::raw(comment.body().to_string()));
+        }
+    }
+    frame.render_widget(
+        Paragraph::new(lines)
+            .block(block)
+            .wrap(Wrap { trim: false }),
+        area,
+    );
+}
+
+fn draw_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+    let line = match app.mode {
+        Mode::Filter => Line::from(format!("Filter: {}▏", app.filter)),
+        Mode::EditTitle => Line::from(format!("Title: {}▏", app.input)),
+        Mode::Comment => Line::from(format!("Comment: {}▏", app.input)),
+        Mode::Normal | Mode::Help => match &app.message {
+            Some(message) => Line::styled(message.clone(), Style::new().fg(Color::Cyan)),
+            None => Line::styled(
+                "? help · Tab view · H/L move · x close · e edit · c comment · / filter · q quit",
+                Style::new().fg(Color::DarkGray),
+            ),
+        },
+    };
+    frame.render_widget(Paragraph::new(line), area);
+}
+
+fn draw_help(frame: &mut Frame) {
+    let height = u16::try_from(HELP.len()).unwrap_or(u16::MAX) + 2;
+    let [area] = Layout::vertical([Constraint::Length(height)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+    let [area] = Layout::horizontal([Constraint::Length(56)])
+        .flex(Flex::Center)
+        .areas(area);
+    let lines: Vec<Line> = HELP
+        .iter()
+        .map(|(keys, action)| {
+            Line::from(vec![
+                Span::styled(format!("{keys:>10}  "), Style::new().bold()),
+                Span::raw(*action),
+            ])
+        })
+        .collect();
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(" Keys ")),
+        area,
+    );
+}